@@ -0,0 +1,400 @@
+//! The abstract syntax tree: a typed version of the parse tree, without the noise.
+//!
+//! `CompilationUnit::lower` builds it from a Joos 1W parse tree (see `lower`). Every node has the
+//! span of its tokens in the source. Names aren't resolved yet: `a.b.c` might be a package, a
+//! type, a field, or a local variable followed by fields, so it's kept as a `Name` until later
+//! passes know which.
+
+use crate::source_map::Span;
+use crate::tokenizer::token_types::Literal;
+use std::fmt;
+
+pub mod lower;
+
+/// A whole file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompilationUnit {
+    pub package: Option<Name>,
+    pub imports: Vec<Import>,
+    pub type_decl: TypeDecl,
+    pub span: Span,
+}
+
+/// `import a.b.C;` or `import a.b.*;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    pub name: Name,
+    /// `import a.b.*;`
+    pub on_demand: bool,
+    pub span: Span,
+}
+
+/// A class or an interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeDecl {
+    pub kind: TypeDeclKind,
+    pub modifiers: Vec<Modifier>,
+    pub name: Ident,
+    /// The superclass of a class (at most one), or the superinterfaces of an interface.
+    pub extends: Vec<Name>,
+    /// Always empty for an interface.
+    pub implements: Vec<Name>,
+    /// Always empty for an interface.
+    pub fields: Vec<FieldDecl>,
+    pub methods: Vec<MethodDecl>,
+    /// Always empty for an interface.
+    pub constructors: Vec<ConstructorDecl>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TypeDeclKind {
+    Class,
+    Interface,
+}
+
+/// The modifiers in Joos. Which ones go together, and on what, is left to later checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Modifier {
+    Public,
+    Protected,
+    Static,
+    Abstract,
+    Final,
+    Native,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDecl {
+    pub modifiers: Vec<Modifier>,
+    pub ty: Type,
+    pub name: Ident,
+    pub init: Option<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodDecl {
+    pub modifiers: Vec<Modifier>,
+    /// `None` for `void`.
+    pub return_type: Option<Type>,
+    pub name: Ident,
+    pub params: Vec<Param>,
+    pub throws: Vec<Name>,
+    /// `None` for an abstract or native method, which ends in `;` instead.
+    pub body: Option<Block>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstructorDecl {
+    pub modifiers: Vec<Modifier>,
+    /// Should be the name of the class.
+    pub name: Ident,
+    pub params: Vec<Param>,
+    pub throws: Vec<Name>,
+    pub body: Block,
+    pub span: Span,
+}
+
+/// A formal parameter of a method or constructor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub ty: Type,
+    pub name: Ident,
+    pub span: Span,
+}
+
+/// `{ ... }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StmtKind {
+    Block(Block),
+    /// `;`
+    Empty,
+    /// An assignment, method call, or `new`, followed by `;`.
+    Expr(Expr),
+    /// `int x = 1;` Joos requires the initializer.
+    LocalVar {
+        ty: Type,
+        name: Ident,
+        init: Expr,
+    },
+    If {
+        cond: Expr,
+        then_branch: Box<Stmt>,
+        else_branch: Option<Box<Stmt>>,
+    },
+    While {
+        cond: Expr,
+        body: Box<Stmt>,
+    },
+    For {
+        /// A `LocalVar` or an `Expr` statement.
+        init: Option<Box<Stmt>>,
+        cond: Option<Expr>,
+        update: Option<Expr>,
+        body: Box<Stmt>,
+    },
+    Return(Option<Expr>),
+    Throw(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    /// Parentheses around the expression aren't included.
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprKind {
+    /// One of the literals in Joos: `Int`, `Bool`, `Char`, `StringLit` or `Null`.
+    Literal(Literal),
+    This,
+    /// A name on its own, e.g. a local variable, or a field access through a package or a type.
+    Name(Name),
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    /// `lhs = rhs`, where `lhs` is a `Name`, `FieldAccess` or `ArrayAccess`.
+    Assign {
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    Cast {
+        ty: Type,
+        expr: Box<Expr>,
+    },
+    InstanceOf {
+        expr: Box<Expr>,
+        ty: Type,
+    },
+    /// `new A(args)`
+    New {
+        class: Name,
+        args: Vec<Expr>,
+    },
+    /// `new T[len]`, where `ty` is `T`.
+    NewArray {
+        ty: Type,
+        len: Box<Expr>,
+    },
+    /// `expr.field`, where `expr` isn't a name. `a.b.c` is a `Name` instead.
+    FieldAccess {
+        expr: Box<Expr>,
+        field: Ident,
+    },
+    /// `receiver.method(args)`, or just `method(args)`. A receiver like `a.b` in `a.b.m()` is a
+    /// `Name`.
+    MethodCall {
+        receiver: Option<Box<Expr>>,
+        method: Ident,
+        args: Vec<Expr>,
+    },
+    ArrayAccess {
+        array: Box<Expr>,
+        index: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    /// `||`
+    Or,
+    /// `&&`
+    And,
+    /// `|`, which is eager on booleans.
+    BitOr,
+    /// `&`, which is eager on booleans.
+    BitAnd,
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Plus,
+    Minus,
+    Times,
+    Divide,
+    Mod,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    /// `-`
+    Minus,
+    /// `!`
+    Not,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Type {
+    pub kind: TypeKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeKind {
+    Primitive(PrimitiveType),
+    /// A class or interface.
+    Named(Name),
+    /// Joos only has one-dimensional arrays, so the element type isn't an array.
+    Array(Box<Type>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrimitiveType {
+    Boolean,
+    Byte,
+    Char,
+    Short,
+    Int,
+}
+
+/// A simple or qualified name, like `x` or `java.util.Vector`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name {
+    /// At least one.
+    pub parts: Vec<Ident>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+impl Name {
+    /// The last part, e.g. `Vector` in `java.util.Vector`.
+    pub fn last(&self) -> &Ident {
+        self.parts.last().unwrap()
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<_> = self.parts.iter().map(|p| p.name.as_str()).collect();
+        f.write_str(&parts.join("."))
+    }
+}
+
+impl fmt::Display for PrimitiveType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            PrimitiveType::Boolean => "boolean",
+            PrimitiveType::Byte => "byte",
+            PrimitiveType::Char => "char",
+            PrimitiveType::Short => "short",
+            PrimitiveType::Int => "int",
+        })
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            TypeKind::Primitive(p) => write!(f, "{}", p),
+            TypeKind::Named(name) => write!(f, "{}", name),
+            TypeKind::Array(elem) => write!(f, "{}[]", elem),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use BinaryOp::*;
+
+        f.write_str(match self {
+            Or => "||",
+            And => "&&",
+            BitOr => "|",
+            BitAnd => "&",
+            Eq => "==",
+            Ne => "!=",
+            Lt => "<",
+            Gt => ">",
+            Le => "<=",
+            Ge => ">=",
+            Plus => "+",
+            Minus => "-",
+            Times => "*",
+            Divide => "/",
+            Mod => "%",
+        })
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            UnaryOp::Minus => "-",
+            UnaryOp::Not => "!",
+        })
+    }
+}
+
+/// Java-like, with parentheses around every operation, to show how it was parsed: e.g.
+/// `(a + (b * c))`.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |args: &[Expr]| {
+            let args: Vec<_> = args.iter().map(Expr::to_string).collect();
+            args.join(", ")
+        };
+        match &self.kind {
+            ExprKind::Literal(Literal::Int(digits)) => f.write_str(digits),
+            ExprKind::Literal(Literal::Bool(b)) => write!(f, "{}", b),
+            ExprKind::Literal(Literal::Char(c)) => write!(f, "{:?}", c),
+            ExprKind::Literal(Literal::StringLit(s)) => write!(f, "{:?}", s),
+            ExprKind::Literal(Literal::Null) => f.write_str("null"),
+            ExprKind::Literal(l) => write!(f, "{:?}", l),
+            ExprKind::This => f.write_str("this"),
+            ExprKind::Name(name) => write!(f, "{}", name),
+            ExprKind::Binary { op, lhs, rhs } => write!(f, "({} {} {})", lhs, op, rhs),
+            ExprKind::Unary { op, operand } => write!(f, "({}{})", op, operand),
+            ExprKind::Assign { lhs, rhs } => write!(f, "({} = {})", lhs, rhs),
+            ExprKind::Cast { ty, expr } => write!(f, "(({}) {})", ty, expr),
+            ExprKind::InstanceOf { expr, ty } => write!(f, "({} instanceof {})", expr, ty),
+            ExprKind::New { class, args } => write!(f, "new {}({})", class, list(args)),
+            ExprKind::NewArray { ty, len } => write!(f, "new {}[{}]", ty, len),
+            ExprKind::FieldAccess { expr, field } => write!(f, "{}.{}", expr, field),
+            ExprKind::MethodCall {
+                receiver: Some(receiver),
+                method,
+                args,
+            } => write!(f, "{}.{}({})", receiver, method, list(args)),
+            ExprKind::MethodCall {
+                receiver: None,
+                method,
+                args,
+            } => write!(f, "{}({})", method, list(args)),
+            ExprKind::ArrayAccess { array, index } => write!(f, "{}[{}]", array, index),
+        }
+    }
+}
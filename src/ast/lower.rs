@@ -0,0 +1,932 @@
+//! Lowering a Joos 1W parse tree to the AST.
+//!
+//! The grammar does most of the work: precedence and associativity are already in the shape of the
+//! tree. What's left is to skip the chains of single-child nodes (like `expression` down to
+//! `primary`), flatten the left-recursive lists, and pick out the parts of each production. Nodes
+//! are told apart by the names of their nonterminals in `joos_1w.cfg`.
+
+use crate::ast::*;
+use crate::diagnostics::Diagnostic;
+use crate::parser::grammar::Grammar;
+use crate::parser::tree::ParseTree;
+use crate::source_map::Span;
+use crate::tokenizer::token_types::{Keyword, Operator};
+use crate::tokenizer::tokens::{Token, TokenInfo};
+use crate::tokenizer::Position;
+use std::fmt;
+
+/// Something the grammar accepts, but Joos doesn't.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LowerError<'a> {
+    /// A cast to something that isn't a type, like `(a + b) c`. The grammar can't tell these
+    /// apart from parenthesized expressions without conflicts, so it accepts both.
+    BadCast {
+        /// The first token between the parentheses.
+        start: Position<'a>,
+        /// Inclusive: the last character of the last token between the parentheses.
+        end: Position<'a>,
+    },
+}
+
+type Res<'a, T> = Result<T, LowerError<'a>>;
+
+impl CompilationUnit {
+    /// Lower a parse tree from `Parser::joos_1w`. `grammar` is the grammar of its table.
+    ///
+    /// Panics if the tree comes from some other grammar.
+    pub fn lower<'a>(tree: &ParseTree<'a>, grammar: &Grammar) -> Res<'a, Self> {
+        let lowerer = Lowerer { grammar };
+        match children(tree) {
+            [_bof, unit, _eof] => lowerer.compilation_unit(unit),
+            _ => lowerer.unexpected(tree),
+        }
+    }
+}
+
+impl<'a> LowerError<'a> {
+    pub fn diagnostic(&self) -> Diagnostic<'a> {
+        match self {
+            LowerError::BadCast { start, end } => {
+                Diagnostic::error(self.to_string(), *start, *end).with_label("not a type")
+            }
+        }
+    }
+}
+
+impl fmt::Display for LowerError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LowerError::BadCast { .. } => {
+                write!(f, "expected a type between the parentheses of a cast")
+            }
+        }
+    }
+}
+
+struct Lowerer<'g> {
+    grammar: &'g Grammar,
+}
+
+impl Lowerer<'_> {
+    /// The nonterminal of a node, or the terminal of a leaf.
+    fn label(&self, tree: &ParseTree) -> &str {
+        self.grammar.name(tree.symbol(self.grammar))
+    }
+
+    fn unexpected(&self, tree: &ParseTree) -> ! {
+        panic!("Not a Joos 1W parse tree: {}", tree.sexp(self.grammar));
+    }
+
+    /// The items of a left-recursive list, like `name_list` or `block_statements`. Separators and
+    /// other terminals are left out.
+    fn list<'t, 'a>(&self, mut tree: &'t ParseTree<'a>) -> Vec<&'t ParseTree<'a>> {
+        let symbol = tree.symbol(self.grammar);
+        let mut items = vec![];
+        loop {
+            let (rest, items_before) = match children(tree) {
+                [first, rest @ ..] if first.symbol(self.grammar) == symbol => (rest, Some(first)),
+                all => (all, None),
+            };
+            items.extend(rest.iter().rev().filter(|c| is_node(c)));
+            match items_before {
+                Some(first) => tree = first,
+                None => break,
+            }
+        }
+        items.reverse();
+        items
+    }
+
+    fn compilation_unit<'a>(&self, tree: &ParseTree<'a>) -> Res<'a, CompilationUnit> {
+        let (package, imports, type_decl) = match children(tree) {
+            [package, imports, type_decl] => (package, imports, type_decl),
+            _ => self.unexpected(tree),
+        };
+        let imports = self.list(imports).into_iter().map(|import| {
+            let children = children(import);
+            Import {
+                name: self.name(&children[1]),
+                on_demand: children.len() == 5,
+                span: span(import),
+            }
+        });
+        Ok(CompilationUnit {
+            package: optional(package).map(|name| self.name(name)),
+            imports: imports.collect(),
+            type_decl: self.type_decl(&children(type_decl)[0])?,
+            span: span(tree),
+        })
+    }
+
+    /// A `class_declaration` or `interface_declaration`.
+    fn type_decl<'a>(&self, tree: &ParseTree<'a>) -> Res<'a, TypeDecl> {
+        let names = |list: &ParseTree| match optional(list) {
+            Some(names) => self.list(names).into_iter().map(|n| self.name(n)).collect(),
+            None => vec![],
+        };
+
+        let mut decl = match (self.label(tree), children(tree)) {
+            ("class_declaration", [modifiers, _, name, extends, implements, _]) => TypeDecl {
+                kind: TypeDeclKind::Class,
+                modifiers: self.modifiers(modifiers),
+                name: ident(name),
+                extends: optional(extends)
+                    .map(|n| self.name(n))
+                    .into_iter()
+                    .collect(),
+                implements: names(implements),
+                fields: vec![],
+                methods: vec![],
+                constructors: vec![],
+                span: span(tree),
+            },
+            ("interface_declaration", [modifiers, _, name, extends, _]) => TypeDecl {
+                kind: TypeDeclKind::Interface,
+                modifiers: self.modifiers(modifiers),
+                name: ident(name),
+                extends: names(extends),
+                implements: vec![],
+                fields: vec![],
+                methods: vec![],
+                constructors: vec![],
+                span: span(tree),
+            },
+            _ => self.unexpected(tree),
+        };
+
+        // The body is `{ members }`.
+        let body = children(tree).last().unwrap();
+        for member in self.list(&children(body)[1]) {
+            // In a class, the members are wrapped in `class_body_declaration`.
+            let member = match (self.label(member), children(member)) {
+                ("class_body_declaration", [member]) => member,
+                _ => member,
+            };
+            match (self.label(member), children(member)) {
+                ("field_declaration", [modifiers, ty, name, rest @ ..]) => {
+                    let init = match rest {
+                        [_, init, _] => Some(self.expr(init)?),
+                        _ => None,
+                    };
+                    decl.fields.push(FieldDecl {
+                        modifiers: self.modifiers(modifiers),
+                        ty: self.ty(ty),
+                        name: ident(name),
+                        init,
+                        span: span(member),
+                    });
+                }
+                ("method_declaration", [header, body]) => {
+                    let body = match children(body) {
+                        [block] if is_node(block) => Some(self.block(block)?),
+                        _ => None,
+                    };
+                    decl.methods.push(self.method(header, body, span(member)));
+                }
+                ("abstract_method_declaration", [header, _]) => {
+                    decl.methods.push(self.method(header, None, span(member)));
+                }
+                ("constructor_declaration", [modifiers, name, _, params, _, throws, body]) => {
+                    decl.constructors.push(ConstructorDecl {
+                        modifiers: self.modifiers(modifiers),
+                        name: ident(name),
+                        params: self.params(params),
+                        throws: self.throws(throws),
+                        body: self.block(body)?,
+                        span: span(member),
+                    });
+                }
+                _ => self.unexpected(member),
+            }
+        }
+        Ok(decl)
+    }
+
+    /// A method, from its `method_header`.
+    fn method(&self, header: &ParseTree, body: Option<Block>, span: Span) -> MethodDecl {
+        match children(header) {
+            [modifiers, return_type, name, _, params, _, throws] => MethodDecl {
+                modifiers: self.modifiers(modifiers),
+                // A leaf here is `void`.
+                return_type: Some(return_type).filter(|t| is_node(t)).map(|t| self.ty(t)),
+                name: ident(name),
+                params: self.params(params),
+                throws: self.throws(throws),
+                body,
+                span,
+            },
+            _ => self.unexpected(header),
+        }
+    }
+
+    fn modifiers(&self, tree: &ParseTree) -> Vec<Modifier> {
+        let modifiers =
+            self.list(tree)
+                .into_iter()
+                .map(|modifier| match token(&children(modifier)[0]).val {
+                    Token::Keyword(Keyword::Public) => Modifier::Public,
+                    Token::Keyword(Keyword::Protected) => Modifier::Protected,
+                    Token::Keyword(Keyword::Static) => Modifier::Static,
+                    Token::Keyword(Keyword::Abstract) => Modifier::Abstract,
+                    Token::Keyword(Keyword::Final) => Modifier::Final,
+                    Token::Keyword(Keyword::Native) => Modifier::Native,
+                    _ => self.unexpected(modifier),
+                });
+        modifiers.collect()
+    }
+
+    fn params(&self, tree: &ParseTree) -> Vec<Param> {
+        let list = match optional(tree) {
+            Some(list) => self.list(list),
+            None => vec![],
+        };
+        let params = list.into_iter().map(|param| match children(param) {
+            [ty, name] => Param {
+                ty: self.ty(ty),
+                name: ident(name),
+                span: span(param),
+            },
+            _ => self.unexpected(param),
+        });
+        params.collect()
+    }
+
+    fn throws(&self, tree: &ParseTree) -> Vec<Name> {
+        match optional(tree) {
+            Some(list) => self.list(list).into_iter().map(|n| self.name(n)).collect(),
+            None => vec![],
+        }
+    }
+
+    fn block<'a>(&self, tree: &ParseTree<'a>) -> Res<'a, Block> {
+        let stmts = match children(tree) {
+            [_, stmts, _] => self.list(stmts),
+            _ => self.unexpected(tree),
+        };
+        Ok(Block {
+            stmts: stmts
+                .into_iter()
+                .map(|s| self.stmt(s))
+                .collect::<Res<'a, _>>()?,
+            span: span(tree),
+        })
+    }
+
+    fn stmt<'a>(&self, tree: &ParseTree<'a>) -> Res<'a, Stmt> {
+        let boxed = |s: &ParseTree<'a>| self.stmt(s).map(Box::new);
+        let kind = match (self.label(tree), children(tree)) {
+            ("block", _) => StmtKind::Block(self.block(tree)?),
+            ("block_statement", [decl, _]) => return self.local_var(decl, span(tree)),
+            ("local_variable_declaration", _) => return self.local_var(tree, span(tree)),
+            (_, [ParseTree::Leaf(_)]) => StmtKind::Empty,
+            ("statement_expression", _) => StmtKind::Expr(self.expr(tree)?),
+            // Chains like `statement` -> `statement_without_trailing_substatement`.
+            (_, [child]) => return self.stmt(child),
+            (_, [expr, _]) if is_node(expr) => StmtKind::Expr(self.expr(expr)?),
+            (_, [_, _]) => StmtKind::Return(None),
+            (_, [keyword, expr, _]) => match token(keyword).val {
+                Token::Keyword(Keyword::Return) => StmtKind::Return(Some(self.expr(expr)?)),
+                _ => StmtKind::Throw(self.expr(expr)?),
+            },
+            (_, [_, _, cond, _, then_branch]) if self.label(tree).starts_with("if") => {
+                StmtKind::If {
+                    cond: self.expr(cond)?,
+                    then_branch: boxed(then_branch)?,
+                    else_branch: None,
+                }
+            }
+            (_, [_, _, cond, _, then_branch, _, else_branch]) => StmtKind::If {
+                cond: self.expr(cond)?,
+                then_branch: boxed(then_branch)?,
+                else_branch: Some(boxed(else_branch)?),
+            },
+            (_, [_, _, cond, _, body]) => StmtKind::While {
+                cond: self.expr(cond)?,
+                body: boxed(body)?,
+            },
+            (_, [_, _, init, _, cond, _, update, _, body]) => StmtKind::For {
+                init: optional(init).map(boxed).transpose()?,
+                cond: optional(cond).map(|c| self.expr(c)).transpose()?,
+                update: optional(update).map(|u| self.expr(u)).transpose()?,
+                body: boxed(body)?,
+            },
+            _ => self.unexpected(tree),
+        };
+        Ok(Stmt {
+            kind,
+            span: span(tree),
+        })
+    }
+
+    /// A `local_variable_declaration`. `span` includes the `;`, if there is one.
+    fn local_var<'a>(&self, tree: &ParseTree<'a>, span: Span) -> Res<'a, Stmt> {
+        match children(tree) {
+            [ty, name, _, init] => Ok(Stmt {
+                kind: StmtKind::LocalVar {
+                    ty: self.ty(ty),
+                    name: ident(name),
+                    init: self.expr(init)?,
+                },
+                span,
+            }),
+            _ => self.unexpected(tree),
+        }
+    }
+
+    fn expr<'a>(&self, tree: &ParseTree<'a>) -> Res<'a, Expr> {
+        let boxed = |e: &ParseTree<'a>| self.expr(e).map(Box::new);
+        let args = |args: &ParseTree<'a>| -> Res<'a, Vec<Expr>> {
+            match optional(args) {
+                Some(list) => self.list(list).into_iter().map(|a| self.expr(a)).collect(),
+                None => Ok(vec![]),
+            }
+        };
+
+        let kind = match (self.label(tree), children(tree)) {
+            ("name", _) => ExprKind::Name(self.name(tree)),
+            ("literal", [literal]) => match &token(literal).val {
+                Token::Literal(l) => ExprKind::Literal(l.clone()),
+                _ => self.unexpected(tree),
+            },
+            ("primary_no_new_array", [ParseTree::Leaf(_)]) => ExprKind::This,
+            // Chains like `expression` -> `assignment_expression` -> ... -> `primary`.
+            (_, [child]) => return self.expr(child),
+            ("primary_no_new_array", [_, expr, _]) => return self.expr(expr),
+            ("assignment", [lhs, _, rhs]) => ExprKind::Assign {
+                lhs: boxed(lhs)?,
+                rhs: boxed(rhs)?,
+            },
+            ("field_access", [expr, _, field]) => ExprKind::FieldAccess {
+                expr: boxed(expr)?,
+                field: ident(field),
+            },
+            ("array_access", [array, _, index, _]) => ExprKind::ArrayAccess {
+                array: boxed(array)?,
+                index: boxed(index)?,
+            },
+            ("method_invocation", [name, _, list, _]) => {
+                // In `a.b.m()`, the receiver is `a.b`.
+                let mut name = self.name(name);
+                let method = name.parts.pop().unwrap();
+                let receiver = match name.parts.last() {
+                    Some(last) => {
+                        name.span = name.parts[0].span.to(last.span);
+                        let span = name.span;
+                        Some(Box::new(Expr {
+                            kind: ExprKind::Name(name),
+                            span,
+                        }))
+                    }
+                    None => None,
+                };
+                ExprKind::MethodCall {
+                    receiver,
+                    method,
+                    args: args(list)?,
+                }
+            }
+            ("method_invocation", [receiver, _, method, _, list, _]) => ExprKind::MethodCall {
+                receiver: Some(boxed(receiver)?),
+                method: ident(method),
+                args: args(list)?,
+            },
+            ("class_instance_creation", [_, class, _, list, _]) => ExprKind::New {
+                class: self.name(class),
+                args: args(list)?,
+            },
+            ("array_creation", [_, ty, _, len, _]) => ExprKind::NewArray {
+                ty: self.ty(ty),
+                len: boxed(len)?,
+            },
+            ("cast_expression", [_, ty, _, expr]) => ExprKind::Cast {
+                ty: match self.label(ty) {
+                    "primitive_type" => self.ty(ty),
+                    _ => self.cast_type(ty)?,
+                },
+                expr: boxed(expr)?,
+            },
+            ("cast_expression", [_, elem, _, rbracket, _, expr]) => ExprKind::Cast {
+                ty: Type {
+                    kind: TypeKind::Array(Box::new(self.ty(elem))),
+                    span: span(elem).to(span(rbracket)),
+                },
+                expr: boxed(expr)?,
+            },
+            (_, [operand, op, ty]) if token(op).val == Token::Keyword(Keyword::Instanceof) => {
+                ExprKind::InstanceOf {
+                    expr: boxed(operand)?,
+                    ty: self.ty(ty),
+                }
+            }
+            (_, [lhs, op, rhs]) => ExprKind::Binary {
+                op: binary_op(token(op)).unwrap_or_else(|| self.unexpected(tree)),
+                lhs: boxed(lhs)?,
+                rhs: boxed(rhs)?,
+            },
+            (_, [op, operand]) => ExprKind::Unary {
+                op: match token(op).val {
+                    Token::Operator(Operator::Minus) => UnaryOp::Minus,
+                    Token::Operator(Operator::Not) => UnaryOp::Not,
+                    _ => self.unexpected(tree),
+                },
+                operand: boxed(operand)?,
+            },
+            _ => self.unexpected(tree),
+        };
+        Ok(Expr {
+            kind,
+            span: span(tree),
+        })
+    }
+
+    /// The type in a cast like `(a.B) x`. The grammar only knows it's an expression, so check
+    /// that it's really a name, with nothing around it.
+    fn cast_type<'a>(&self, expr: &ParseTree<'a>) -> Res<'a, Type> {
+        let mut tree = expr;
+        while self.label(tree) != "name" {
+            match children(tree) {
+                [child] if is_node(child) => tree = child,
+                _ => {
+                    let tokens = expr.tokens();
+                    let (first, last) = (tokens[0], tokens[tokens.len() - 1]);
+                    return Err(LowerError::BadCast {
+                        start: first.start,
+                        end: end_inclusive(last),
+                    });
+                }
+            }
+        }
+        Ok(self.ty(tree))
+    }
+
+    fn ty(&self, tree: &ParseTree) -> Type {
+        let kind = match (self.label(tree), children(tree)) {
+            ("name", _) => TypeKind::Named(self.name(tree)),
+            ("primitive_type", [keyword]) => TypeKind::Primitive(match token(keyword).val {
+                Token::Keyword(Keyword::Boolean) => PrimitiveType::Boolean,
+                Token::Keyword(Keyword::Byte) => PrimitiveType::Byte,
+                Token::Keyword(Keyword::Char) => PrimitiveType::Char,
+                Token::Keyword(Keyword::Short) => PrimitiveType::Short,
+                Token::Keyword(Keyword::Int) => PrimitiveType::Int,
+                _ => self.unexpected(tree),
+            }),
+            ("array_type", [elem, _, _]) => TypeKind::Array(Box::new(self.ty(elem))),
+            (_, [child]) => return self.ty(child),
+            _ => self.unexpected(tree),
+        };
+        Type {
+            kind,
+            span: span(tree),
+        }
+    }
+
+    /// A `name`, like `a.b.c`.
+    fn name(&self, tree: &ParseTree) -> Name {
+        let mut parts = vec![];
+        let mut rest = tree;
+        loop {
+            match children(rest) {
+                [id] => {
+                    parts.push(ident(id));
+                    break;
+                }
+                [prefix, _, id] => {
+                    parts.push(ident(id));
+                    rest = prefix;
+                }
+                _ => self.unexpected(tree),
+            }
+        }
+        parts.reverse();
+        Name {
+            parts,
+            span: span(tree),
+        }
+    }
+}
+
+fn children<'t, 'a>(tree: &'t ParseTree<'a>) -> &'t [ParseTree<'a>] {
+    match tree {
+        ParseTree::Node { children, .. } => children,
+        _ => &[],
+    }
+}
+
+fn is_node(tree: &ParseTree) -> bool {
+    matches!(tree, ParseTree::Node { .. })
+}
+
+/// The first child that's a node, if any. This is how the optional parts of the grammar are
+/// written, e.g. `super` is either empty, or `EXTENDS name`.
+fn optional<'t, 'a>(tree: &'t ParseTree<'a>) -> Option<&'t ParseTree<'a>> {
+    children(tree).iter().find(|c| is_node(c))
+}
+
+/// The span of a tree with at least one token.
+fn span(tree: &ParseTree) -> Span {
+    tree.span().expect("Expected a non-empty parse tree")
+}
+
+fn token<'t, 'a>(tree: &'t ParseTree<'a>) -> &'t TokenInfo<'a> {
+    match tree {
+        ParseTree::Leaf(token) => token,
+        _ => panic!("Expected a token, found {:?}", tree),
+    }
+}
+
+fn ident(tree: &ParseTree) -> Ident {
+    let token = token(tree);
+    match &token.val {
+        Token::Identifier(name) => Ident {
+            name: name.to_string(),
+            span: token.span(),
+        },
+        _ => panic!("Expected an identifier, found {:?}", token),
+    }
+}
+
+fn binary_op(token: &TokenInfo) -> Option<BinaryOp> {
+    use Operator::*;
+
+    let op = match token.val {
+        Token::Operator(op) => op,
+        _ => return None,
+    };
+    Some(match op {
+        Or => BinaryOp::Or,
+        And => BinaryOp::And,
+        BitOr => BinaryOp::BitOr,
+        BitAnd => BinaryOp::BitAnd,
+        Eq => BinaryOp::Eq,
+        Ne => BinaryOp::Ne,
+        Lt => BinaryOp::Lt,
+        Gt => BinaryOp::Gt,
+        Le => BinaryOp::Le,
+        Ge => BinaryOp::Ge,
+        Plus => BinaryOp::Plus,
+        Minus => BinaryOp::Minus,
+        Star => BinaryOp::Times,
+        Divide => BinaryOp::Divide,
+        Mod => BinaryOp::Mod,
+        _ => return None,
+    })
+}
+
+/// The position of the last character of a token.
+fn end_inclusive<'a>(token: &TokenInfo<'a>) -> Position<'a> {
+    let last = token.lexeme.char_indices().last().map_or(0, |(i, _)| i);
+    Position {
+        col: token.start.col + last,
+        ..token.start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::ColorMode;
+    use crate::parser::Parser;
+    use crate::source_map::SourceMap;
+    use crate::test_util::method;
+    use crate::tokenizer::{Text, Tokenizer};
+
+    fn lower(src: &str) -> Result<CompilationUnit, String> {
+        let tokens = Tokenizer::global().tokenize(Text(src)).map(Result::unwrap);
+        let parser = Parser::global();
+        let tree = parser.parse(tokens).map_err(|e| e.to_string())?;
+        CompilationUnit::lower(&tree, parser.table().grammar()).map_err(|e| e.to_string())
+    }
+
+    fn stmts(unit: &CompilationUnit) -> &[Stmt] {
+        &unit.type_decl.methods[0].body.as_ref().unwrap().stmts
+    }
+
+    fn snippet(src: &str, span: Span) -> &str {
+        &src[span.lo as usize..span.hi as usize]
+    }
+
+    #[test]
+    fn expressions() {
+        for (expr, expected) in [
+            (
+                "a || b && c | d & e == f",
+                "(a || (b && (c | (d & (e == f)))))",
+            ),
+            (
+                "a != b <= c - d / e % f",
+                "(a != (b <= (c - ((d / e) % f))))",
+            ),
+            ("a - b - c", "((a - b) - c)"),
+            ("a - (b - c)", "(a - (b - c))"),
+            ("-a * !b", "((-a) * (!b))"),
+            ("- -2147483648", "(-(-2147483648))"),
+            ("x = y = 1", "(x = (y = 1))"),
+            ("x instanceof A[] == true", "((x instanceof A[]) == true)"),
+            ("(int) -x", "((int) (-x))"),
+            ("(a) - x", "(a - x)"),
+            ("(java.lang.Object) x", "((java.lang.Object) x)"),
+            ("(int[]) (A[]) !x", "((int[]) ((A[]) (!x)))"),
+            ("a.b.c(d)[e].f", "a.b.c(d)[e].f"),
+            ("m() + this.n(1, 2)", "(m() + this.n(1, 2))"),
+            ("new A(1, 'c').x", "new A(1, 'c').x"),
+            ("new int[n + 1].length", "new int[(n + 1)].length"),
+            ("new a.B[5]", "new a.B[5]"),
+            ("\"a\\n\" + null", "(\"a\\n\" + null)"),
+        ] {
+            let src = method(&format!("r = {};", expr));
+            let unit = lower(&src).unwrap();
+            match &stmts(&unit)[0].kind {
+                StmtKind::Expr(Expr {
+                    kind: ExprKind::Assign { rhs, .. },
+                    ..
+                }) => assert_eq!(expected, rhs.to_string(), "{:?}", expr),
+                kind => panic!("{:?}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn literals_and_names() {
+        use crate::tokenizer::token_types::Literal;
+
+        let src = method("x = 'a'; x = \"\\t\"; x = 42; x = false; x = null; a.b.c = 1;");
+        let unit = lower(&src).unwrap();
+        let rhs: Vec<_> = stmts(&unit)
+            .iter()
+            .map(|s| match &s.kind {
+                StmtKind::Expr(Expr {
+                    kind: ExprKind::Assign { lhs, rhs },
+                    ..
+                }) => (lhs, rhs),
+                kind => panic!("{:?}", kind),
+            })
+            .collect();
+        let literals: Vec<_> = rhs[..5].iter().map(|(_, rhs)| &rhs.kind).collect();
+        assert_eq!(
+            vec![
+                &ExprKind::Literal(Literal::Char('a')),
+                &ExprKind::Literal(Literal::StringLit(String::from("\t"))),
+                &ExprKind::Literal(Literal::Int(String::from("42"))),
+                &ExprKind::Literal(Literal::Bool(false)),
+                &ExprKind::Literal(Literal::Null),
+            ],
+            literals
+        );
+
+        // A field access through a name stays a name, for later passes to sort out.
+        match &rhs[5].0.kind {
+            ExprKind::Name(name) => {
+                assert_eq!("a.b.c", name.to_string());
+                assert_eq!("c", name.last().name);
+                assert_eq!("a.b.c", snippet(&src, name.span));
+                assert_eq!("b", snippet(&src, name.parts[1].span));
+            }
+            kind => panic!("{:?}", kind),
+        }
+
+        // Names are compared after unicode escape translation.
+        let src = method(r"\u0078.y = 1;");
+        let unit = lower(&src).unwrap();
+        match &stmts(&unit)[0].kind {
+            StmtKind::Expr(Expr {
+                kind: ExprKind::Assign { lhs, .. },
+                ..
+            }) => {
+                assert_eq!("x.y", lhs.to_string());
+                assert_eq!(r"\u0078.y", snippet(&src, lhs.span));
+            }
+            kind => panic!("{:?}", kind),
+        }
+    }
+
+    #[test]
+    fn spans() {
+        let src = method("r = (a + b) * c.d(e);");
+        let unit = lower(&src).unwrap();
+        let (lhs, rhs) = match &stmts(&unit)[0].kind {
+            StmtKind::Expr(Expr {
+                kind: ExprKind::Assign { rhs, .. },
+                ..
+            }) => match &rhs.kind {
+                ExprKind::Binary { lhs, rhs, .. } => (lhs, rhs),
+                kind => panic!("{:?}", kind),
+            },
+            kind => panic!("{:?}", kind),
+        };
+        assert_eq!("a + b", snippet(&src, lhs.span));
+        assert_eq!("c.d(e)", snippet(&src, rhs.span));
+        match &rhs.kind {
+            ExprKind::MethodCall {
+                receiver: Some(receiver),
+                method,
+                ..
+            } => {
+                assert_eq!("c", snippet(&src, receiver.span));
+                assert_eq!("d", snippet(&src, method.span));
+            }
+            kind => panic!("{:?}", kind),
+        }
+        assert_eq!("r = (a + b) * c.d(e);", snippet(&src, stmts(&unit)[0].span));
+        assert_eq!(src, snippet(&src, unit.span));
+    }
+
+    #[test]
+    fn statements() {
+        let src = method(
+            "int[] a = new int[3]; \
+             if (x) if (y) ; else { return; } \
+             while (i < 3) i = i + 1; \
+             for (int i = 0; ; i = i + 1) m(); \
+             for (;;) {} \
+             throw e; \
+             return a;",
+        );
+        let unit = lower(&src).unwrap();
+        let stmts = stmts(&unit);
+        assert_eq!(7, stmts.len());
+
+        match &stmts[0].kind {
+            StmtKind::LocalVar { ty, name, init } => {
+                assert_eq!("int[]", ty.to_string());
+                assert_eq!("a", name.name);
+                assert_eq!("new int[3]", init.to_string());
+            }
+            kind => panic!("{:?}", kind),
+        }
+        assert_eq!("int[] a = new int[3];", snippet(&src, stmts[0].span));
+
+        // The `else` goes with the inner `if`.
+        match &stmts[1].kind {
+            StmtKind::If {
+                then_branch,
+                else_branch: None,
+                ..
+            } => match &then_branch.kind {
+                StmtKind::If {
+                    cond,
+                    then_branch,
+                    else_branch: Some(else_branch),
+                } => {
+                    assert_eq!("y", cond.to_string());
+                    assert_eq!(StmtKind::Empty, then_branch.kind);
+                    assert!(matches!(else_branch.kind, StmtKind::Block(_)));
+                }
+                kind => panic!("{:?}", kind),
+            },
+            kind => panic!("{:?}", kind),
+        }
+
+        assert!(matches!(stmts[2].kind, StmtKind::While { .. }));
+        match &stmts[3].kind {
+            StmtKind::For {
+                init: Some(init),
+                cond: None,
+                update: Some(update),
+                body,
+            } => {
+                assert!(matches!(init.kind, StmtKind::LocalVar { .. }));
+                assert_eq!("int i = 0", snippet(&src, init.span));
+                assert_eq!("(i = (i + 1))", update.to_string());
+                assert!(matches!(body.kind, StmtKind::Expr(_)));
+            }
+            kind => panic!("{:?}", kind),
+        }
+        assert!(matches!(
+            stmts[4].kind,
+            StmtKind::For {
+                init: None,
+                cond: None,
+                update: None,
+                ..
+            }
+        ));
+        assert!(matches!(stmts[5].kind, StmtKind::Throw(_)));
+        assert!(matches!(stmts[6].kind, StmtKind::Return(Some(_))));
+    }
+
+    #[test]
+    fn declarations() {
+        let src = "\
+package a.b;
+import java.util.Vector;
+import java.io.*;
+public abstract class A extends B implements C, d.E {
+    public A(int x, A[] y) throws Exception {}
+    protected static int x = 1;
+    public Vector v;
+    public abstract void m();
+    public static native int n(int i);
+    public final boolean[] o() { return null; }
+}
+";
+        let unit = lower(src).unwrap();
+        assert_eq!("a.b", unit.package.as_ref().unwrap().to_string());
+        let imports: Vec<_> = (unit.imports.iter())
+            .map(|i| (i.name.to_string(), i.on_demand))
+            .collect();
+        assert_eq!(
+            vec![
+                (String::from("java.util.Vector"), false),
+                (String::from("java.io"), true)
+            ],
+            imports
+        );
+        assert_eq!("import java.io.*;", snippet(src, unit.imports[1].span));
+
+        let class = &unit.type_decl;
+        assert_eq!(TypeDeclKind::Class, class.kind);
+        assert_eq!(vec![Modifier::Public, Modifier::Abstract], class.modifiers);
+        assert_eq!("A", class.name.name);
+        let names = |names: &[Name]| names.iter().map(Name::to_string).collect::<Vec<_>>();
+        assert_eq!(vec!["B"], names(&class.extends));
+        assert_eq!(vec!["C", "d.E"], names(&class.implements));
+
+        let constructor = &class.constructors[0];
+        assert_eq!(1, class.constructors.len());
+        assert_eq!(vec!["Exception"], names(&constructor.throws));
+        let params: Vec<_> = (constructor.params.iter())
+            .map(|p| format!("{} {}", p.ty, p.name))
+            .collect();
+        assert_eq!(vec!["int x", "A[] y"], params);
+
+        let fields: Vec<_> = (class.fields.iter())
+            .map(|f| (f.name.to_string(), f.ty.to_string(), f.init.is_some()))
+            .collect();
+        assert_eq!(
+            vec![
+                (String::from("x"), String::from("int"), true),
+                (String::from("v"), String::from("Vector"), false)
+            ],
+            fields
+        );
+        assert_eq!(
+            vec![Modifier::Protected, Modifier::Static],
+            class.fields[0].modifiers
+        );
+
+        let methods: Vec<_> = (class.methods.iter())
+            .map(|m| {
+                let ty = m.return_type.as_ref().map(Type::to_string);
+                (m.name.to_string(), ty, m.body.is_some())
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (String::from("m"), None, false),
+                (String::from("n"), Some(String::from("int")), false),
+                (String::from("o"), Some(String::from("boolean[]")), true),
+            ],
+            methods
+        );
+        assert_eq!(
+            "public abstract void m();",
+            snippet(src, class.methods[0].span)
+        );
+
+        let src = "public interface I extends J, K { int m(); void n(I i) throws E; }";
+        let interface = lower(src).unwrap().type_decl;
+        assert_eq!(TypeDeclKind::Interface, interface.kind);
+        assert_eq!(vec!["J", "K"], names(&interface.extends));
+        assert_eq!(2, interface.methods.len());
+        assert_eq!(
+            "void n(I i) throws E;",
+            snippet(src, interface.methods[1].span)
+        );
+    }
+
+    #[test]
+    fn bad_casts() {
+        for expr in ["(a + b) c", "((A)) x", "(a.b()) c", "(this) x", "(1) x"] {
+            let src = method(&format!("r = {};", expr));
+            assert_eq!(
+                Err(String::from(
+                    "expected a type between the parentheses of a cast"
+                )),
+                lower(&src),
+                "{:?}",
+                expr
+            );
+        }
+
+        let mut map = SourceMap::new();
+        let file = map.add_file("A.java", method("r = (a +\n  b) !c;"));
+        let tokens = Tokenizer::global()
+            .tokenize(map.file(file))
+            .map(Result::unwrap);
+        let parser = Parser::global();
+        let tree = parser.parse(tokens).unwrap();
+        let err = CompilationUnit::lower(&tree, parser.table().grammar()).unwrap_err();
+        assert_eq!(
+            "\
+error: expected a type between the parentheses of a cast
+ --> A.java:1:55
+  |
+1 |   public class A { public A() {} public void m() { r = (a +
+  |  _______________________________________________________^
+2 | |   b) !c; } }
+  | |___^ not a type
+",
+            map.render(&err.diagnostic(), ColorMode::Plain)
+        );
+    }
+}
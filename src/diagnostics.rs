@@ -0,0 +1,360 @@
+//! This module renders error messages for the user, in the style of `rustc`.
+//!
+//! For example:
+//! ```text
+//! error: char literal too long; expected a single char, found 'ab'
+//!  --> Foo.java:2:10
+//!   |
+//! 2 | char c = 'ab';
+//!   |          ^^^^
+//!   |
+//!   = help: string literals use double quotes: "ab"
+//! ```
+
+use crate::tokenizer::Position;
+use std::fmt::Write;
+
+/// How serious is the problem?
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+/// Whether or not to use ANSI escape codes to color the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    Plain,
+    Ansi,
+}
+
+/// A message for the user, pointing at a span of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic<'a> {
+    pub level: Level,
+    pub message: String,
+    pub start: Position<'a>,
+    /// Inclusive! May be on a later line than `start`.
+    pub end_inclusive: Position<'a>,
+    /// A short message printed next to the underline.
+    pub label: Option<String>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl<'a> Diagnostic<'a> {
+    /// An error pointing at the span from `start` to `end_inclusive`.
+    pub fn error(
+        message: impl Into<String>,
+        start: Position<'a>,
+        end_inclusive: Position<'a>,
+    ) -> Self {
+        Self {
+            level: Level::Error,
+            message: message.into(),
+            start,
+            end_inclusive,
+            label: None,
+            notes: vec![],
+            help: None,
+        }
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Render the diagnostic as a multi-line string, ending in a newline.
+    ///
+    /// Lines and columns are reported one-indexed, as is customary.
+    pub fn render(&self, file_name: &str, mode: ColorMode) -> String {
+        let style = Style { mode };
+        let (start, end) = (self.start, self.end_inclusive);
+        debug_assert!((start.line_num, start.col) <= (end.line_num, end.col));
+
+        // Width of the line-number gutter.
+        let width = (end.line_num + 1).to_string().len();
+        let pad = " ".repeat(width);
+        let bar = style.gutter("|");
+
+        let mut out = String::new();
+        let level = match self.level {
+            Level::Error => style.error("error"),
+            Level::Warning => style.warning("warning"),
+        };
+        writeln!(
+            out,
+            "{}{}",
+            level,
+            style.bold(&format!(": {}", self.message))
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "{}{} {}:{}:{}",
+            pad,
+            style.gutter("-->"),
+            file_name,
+            start.line_num + 1,
+            // Count chars, not bytes.
+            start.line[..start.col].chars().count() + 1
+        )
+        .unwrap();
+        writeln!(out, "{} {}", pad, bar).unwrap();
+
+        let label = match &self.label {
+            Some(l) => format!(" {}", l),
+            None => String::new(),
+        };
+        let line_num = |n: usize| style.gutter(&format!("{:>width$}", n + 1, width = width));
+
+        if start.line_num == end.line_num {
+            // E.g.:
+            // 2 | char c = 'ab';
+            //   |          ^^^^ label
+            let offset = display_width(&start.line[..start.col]);
+            let len = span_width(start, end);
+            writeln!(
+                out,
+                "{} {} {}",
+                line_num(start.line_num),
+                bar,
+                expand(start.line)
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "{} {} {}{}",
+                pad,
+                bar,
+                " ".repeat(offset),
+                style.error(&format!("{}{}", "^".repeat(len), label))
+            )
+            .unwrap();
+        } else {
+            // E.g.:
+            // 3 |   x /* foo
+            //   |  _____^
+            // ...
+            // 9 | |   bar
+            //   | |_____^ label
+            let offset = display_width(&start.line[..start.col]);
+            writeln!(
+                out,
+                "{} {}   {}",
+                line_num(start.line_num),
+                bar,
+                expand(start.line)
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "{} {} {}",
+                pad,
+                bar,
+                style.error(&format!(" {}^", "_".repeat(offset + 1)))
+            )
+            .unwrap();
+
+            // We only have access to the first and last lines of the span, so the middle is
+            // elided. (This is also what rustc does for long spans.)
+            if end.line_num > start.line_num + 1 {
+                writeln!(out, "{}", style.gutter("...")).unwrap();
+            }
+
+            let offset = display_width(&end.line[..end.col]) + 1;
+            writeln!(
+                out,
+                "{} {} {} {}",
+                line_num(end.line_num),
+                bar,
+                style.error("|"),
+                expand(end.line)
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "{} {} {}",
+                pad,
+                bar,
+                style.error(&format!("|{}^{}", "_".repeat(offset), label))
+            )
+            .unwrap();
+        }
+
+        if !self.notes.is_empty() || self.help.is_some() {
+            writeln!(out, "{} {}", pad, bar).unwrap();
+        }
+        for note in &self.notes {
+            writeln!(
+                out,
+                "{} {} {}",
+                pad,
+                style.gutter("="),
+                note_line("note", note)
+            )
+            .unwrap();
+        }
+        if let Some(help) = &self.help {
+            writeln!(
+                out,
+                "{} {} {}",
+                pad,
+                style.gutter("="),
+                note_line("help", help)
+            )
+            .unwrap();
+        }
+
+        out
+    }
+}
+
+/// Format a trailing note or help message.
+fn note_line(kind: &str, msg: &str) -> String {
+    format!("{}: {}", kind, msg)
+}
+
+/// The number of columns taken up by the text from `start` up to and including `end`, which must
+/// be on the same line.
+///
+/// `end` may be 1 past the end of the line, referring to the (implicit) newline character, which
+/// takes up one column.
+fn span_width(start: Position, end: Position) -> usize {
+    let newline = if end.col == end.line.len() { 1 } else { 0 };
+    let text = &start.line[start.col..end.col + end.width()];
+    display_width(text) + newline
+}
+
+/// Tabs are rendered as this many spaces, so that underlines line up with the source text.
+const TAB_WIDTH: usize = 4;
+
+/// Replace tabs with spaces.
+fn expand(line: &str) -> String {
+    line.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+/// The number of columns `s` takes up on the terminal, after expanding tabs.
+fn display_width(s: &str) -> usize {
+    s.chars()
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+/// Helper for applying ANSI styles, if enabled.
+struct Style {
+    mode: ColorMode,
+}
+
+impl Style {
+    fn paint(&self, codes: &str, s: &str) -> String {
+        match self.mode {
+            ColorMode::Plain => String::from(s),
+            ColorMode::Ansi => format!("\x1b[{}m{}\x1b[0m", codes, s),
+        }
+    }
+
+    fn bold(&self, s: &str) -> String {
+        self.paint("1", s)
+    }
+
+    fn error(&self, s: &str) -> String {
+        self.paint("1;31", s)
+    }
+
+    fn warning(&self, s: &str) -> String {
+        self.paint("1;33", s)
+    }
+
+    fn gutter(&self, s: &str) -> String {
+        self.paint("1;34", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source_map::FileId;
+
+    fn pos(lines: &[&'static str], line_num: usize, col: usize) -> Position<'static> {
+        Position {
+            file: FileId::ANONYMOUS,
+            line: lines[line_num],
+            line_num,
+            line_start: lines[..line_num].iter().map(|l| l.len() + 1).sum(),
+            col,
+        }
+    }
+
+    #[test]
+    fn single_line() {
+        let lines = ["int x;", "char c = 'ab';"];
+        let d = Diagnostic::error(
+            "char literal too long",
+            pos(&lines, 1, 9),
+            pos(&lines, 1, 12),
+        )
+        .with_label("expected one char")
+        .with_note("char literals hold exactly one char")
+        .with_help("string literals use double quotes");
+
+        let expected = "\
+error: char literal too long
+ --> A.java:2:10
+  |
+2 | char c = 'ab';
+  |          ^^^^ expected one char
+  |
+  = note: char literals hold exactly one char
+  = help: string literals use double quotes
+";
+        assert_eq!(expected, d.render("A.java", ColorMode::Plain));
+    }
+
+    #[test]
+    fn multi_line() {
+        let lines = ["", "  x /* foo", "bar", "", "", "", "", "", "", "baz"];
+        let d = Diagnostic::error("unclosed comment", pos(&lines, 1, 4), pos(&lines, 9, 2))
+            .with_label("input ends here");
+
+        let expected = "\
+error: unclosed comment
+  --> A.java:2:5
+   |
+ 2 |     x /* foo
+   |  _____^
+...
+10 | | baz
+   | |___^ input ends here
+";
+        assert_eq!(expected, d.render("A.java", ColorMode::Plain));
+    }
+
+    #[test]
+    fn tabs_and_colors() {
+        let lines = ["\tx # y"];
+        let d = Diagnostic::error("not a token", pos(&lines, 0, 3), pos(&lines, 0, 3));
+
+        let plain = d.render("A.java", ColorMode::Plain);
+        assert!(
+            plain.contains("\n1 |     x # y\n  |       ^\n"),
+            "{}",
+            plain
+        );
+
+        let colored = d.render("A.java", ColorMode::Ansi);
+        assert!(colored.starts_with("\x1b[1;31merror\x1b[0m"), "{}", colored);
+        assert!(colored.contains("\x1b[1;31m^\x1b[0m"), "{}", colored);
+    }
+}
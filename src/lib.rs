@@ -1 +1,8 @@
+pub mod ast;
+pub mod diagnostics;
+pub mod parser;
+pub mod source_map;
 pub mod tokenizer;
+
+#[cfg(test)]
+mod test_util;
@@ -1,17 +1,56 @@
+use cs444::ast::CompilationUnit;
+use cs444::diagnostics::ColorMode;
+use cs444::parser::Parser;
+use cs444::source_map::SourceMap;
 use cs444::tokenizer::Tokenizer;
 use std::error::Error;
-use std::io::{self, prelude::*};
+use std::io::{self, IsTerminal};
+use std::process;
 
 type Res<T> = Result<T, Box<dyn Error>>;
 
+/// The exit code the course's test harness expects when a program is rejected.
+const REJECT_EXIT_CODE: i32 = 42;
+
 // todo: This is just placeholder code for now.
-// It reads stdin, and then runs the tokenizer on it.
+// It reads stdin, runs the tokenizer and the parser on it, and prints the AST.
 fn main() -> Res<()> {
-    let lines: Vec<String> = io::stdin().lock().lines().collect::<Result<_, _>>()?;
+    let mut map = SourceMap::new();
+    let file = map.read_file("<stdin>", io::stdin().lock())?;
 
     let t = Tokenizer::new();
-    for token in t.tokenize(lines.iter().map(String::as_str)) {
-        println!("{:?}", token);
+    let (tokens, mut errors) = t.tokenize_all(map.file(file));
+    errors.extend(t.check_supported(&tokens));
+
+    let color = if io::stderr().is_terminal() {
+        ColorMode::Ansi
+    } else {
+        ColorMode::Plain
+    };
+    for e in &errors {
+        eprintln!("{}", map.render(&e.diagnostic(), color));
+    }
+    if !errors.is_empty() {
+        process::exit(REJECT_EXIT_CODE);
+    }
+
+    let parser = Parser::global();
+    let tree = match parser.parse(tokens) {
+        Ok(tree) => tree,
+        Err(e) => {
+            match e.diagnostic() {
+                Some(diagnostic) => eprintln!("{}", map.render(&diagnostic, color)),
+                None => eprintln!("error: {}", e),
+            }
+            process::exit(REJECT_EXIT_CODE);
+        }
+    };
+    match CompilationUnit::lower(&tree, parser.table().grammar()) {
+        Ok(unit) => println!("{:#?}", unit),
+        Err(e) => {
+            eprintln!("{}", map.render(&e.diagnostic(), color));
+            process::exit(REJECT_EXIT_CODE);
+        }
     }
 
     Ok(())
@@ -0,0 +1,555 @@
+//! Parsing: turning a stream of tokens into a parse tree.
+//!
+//! The parser is driven by an LALR(1) table (see `lalr`), built from a context-free grammar in
+//! the course's `.cfg` format (see `grammar`). Its terminals are kinds of tokens, named by
+//! `TokenKind::name`.
+//!
+//! Tables can also be read from, and written to, the `.lr1` format of the course's Jlalr tool (see
+//! `lr1`).
+//!
+//! The grammar of Joos 1W is in `parser/joos_1w.cfg`; `Parser::joos_1w` builds a parser for it.
+//!
+//! ```
+//! # use cs444::parser::{grammar::Grammar, lalr::ParseTable, Parser};
+//! # use cs444::tokenizer::{Text, Tokenizer};
+//! let cfg = "4\nBOF\nEOF\nID\nPLUS\n2\nS\nexpr\nS\n3\nS BOF expr EOF\nexpr expr PLUS ID\nexpr ID\n";
+//! let table = ParseTable::build(Grammar::parse(cfg).unwrap()).unwrap();
+//! let parser = Parser::new(table);
+//!
+//! let tokens = Tokenizer::global().tokenize(Text("a + b")).map(Result::unwrap);
+//! let tree = parser.parse(tokens).unwrap();
+//! assert_eq!(
+//!     "(S BOF (expr (expr a) + b) EOF)",
+//!     tree.sexp(parser.table().grammar())
+//! );
+//! ```
+
+use crate::diagnostics::Diagnostic;
+use crate::tokenizer::token_types::TokenKind;
+use crate::tokenizer::tokens::TokenInfo;
+use crate::tokenizer::Position;
+use grammar::{Grammar, ProductionId, Terminal};
+use lalr::{Action, ParseTable, StateId};
+use std::fmt;
+use std::iter;
+use std::sync::OnceLock;
+use tree::ParseTree;
+
+pub mod grammar;
+pub mod lalr;
+pub mod lr1;
+pub mod tree;
+
+/// The syntax of Joos 1W, in the `.cfg` format. It follows the LALR(1) grammar of the Java
+/// Language Specification, cut down to what Joos has. Some things it accepts still have to be
+/// rejected later, e.g. bad combinations of modifiers, and casts to things other than types.
+const JOOS_1W_GRAMMAR: &str = include_str!("parser/joos_1w.cfg");
+
+/// A table-driven LR parser.
+#[derive(Debug)]
+pub struct Parser {
+    table: ParseTable,
+}
+
+/// An error encountered while parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError<'a> {
+    /// A token that doesn't fit the grammar.
+    UnexpectedToken {
+        token: Box<TokenInfo<'a>>,
+        /// The terminals that would have been fine instead.
+        expected: Vec<Terminal>,
+    },
+    /// The input ended too early.
+    UnexpectedEnd {
+        /// The last token of the input, if there were any.
+        last: Option<Box<TokenInfo<'a>>>,
+        expected: Vec<Terminal>,
+    },
+    /// The table led the parser astray: e.g. it's missing a goto, or it can't complete the start
+    /// production at the end of the input. This can't happen with a table from `ParseTable::build`,
+    /// as long as `EOF` only appears at the end of the start production.
+    BadTable { state: StateId },
+}
+
+impl Parser {
+    pub fn new(table: ParseTable) -> Self {
+        Self { table }
+    }
+
+    /// Build the parse table for Joos 1W. This takes a few milliseconds.
+    pub fn joos_1w() -> Self {
+        let grammar =
+            Grammar::parse(JOOS_1W_GRAMMAR).unwrap_or_else(|e| panic!("joos_1w.cfg: {}", e));
+        let table = ParseTable::build(grammar).unwrap_or_else(|conflicts| {
+            panic!("joos_1w.cfg: {}", conflicts[0]);
+        });
+        Self::new(table)
+    }
+
+    /// A shared Joos 1W parser, built the first time it's needed.
+    pub fn global() -> &'static Parser {
+        static PARSER: OnceLock<Parser> = OnceLock::new();
+        PARSER.get_or_init(Parser::joos_1w)
+    }
+
+    pub fn table(&self) -> &ParseTable {
+        &self.table
+    }
+
+    /// Parse a whole file. The parser adds the `BOF` and `EOF` markers itself, so `tokens` shouldn't
+    /// include them.
+    ///
+    /// Parsing stops at the first error.
+    pub fn parse<'a>(
+        &self,
+        tokens: impl IntoIterator<Item = TokenInfo<'a>>,
+    ) -> Result<ParseTree<'a>, ParseError<'a>> {
+        let grammar = self.table.grammar();
+
+        // The states, and the trees for the symbols between them.
+        let mut states = vec![StateId::INIT];
+        let mut trees = vec![];
+        let mut last = None;
+
+        let input = iter::once(ParseTree::Marker(Terminal::Bof))
+            .chain(tokens.into_iter().map(ParseTree::Leaf))
+            .chain(iter::once(ParseTree::Marker(Terminal::Eof)));
+        for leaf in input {
+            let t = match &leaf {
+                ParseTree::Leaf(token) => Terminal::Token(token.val.kind()),
+                ParseTree::Marker(t) => *t,
+                ParseTree::Node { .. } => unreachable!(),
+            };
+
+            loop {
+                let state = *states.last().unwrap();
+                match self.table.action(state, t) {
+                    Some(Action::Shift(next)) => {
+                        states.push(next);
+                        break;
+                    }
+                    Some(Action::Reduce(p)) => self.reduce(p, &mut states, &mut trees)?,
+                    None => {
+                        let expected = self.table.expected(state);
+                        return Err(match leaf {
+                            ParseTree::Leaf(token) => ParseError::UnexpectedToken {
+                                token: Box::new(token),
+                                expected,
+                            },
+                            _ if t == Terminal::Eof => ParseError::UnexpectedEnd { last, expected },
+                            _ => ParseError::BadTable { state },
+                        });
+                    }
+                }
+            }
+
+            if let ParseTree::Leaf(token) = &leaf {
+                last = Some(Box::new(token.clone()));
+            }
+            trees.push(leaf);
+        }
+
+        // All that's left is to reduce by the start production.
+        let state = *states.last().unwrap();
+        let start = grammar.productions_of(grammar.start()).iter().find(|&&p| {
+            let rhs = &grammar.production(p).rhs;
+            rhs.len() == trees.len() && rhs.iter().zip(&trees).all(|(&s, t)| s == t.symbol(grammar))
+        });
+        match start {
+            Some(&production) => Ok(ParseTree::Node {
+                production,
+                children: trees,
+            }),
+            None => Err(ParseError::BadTable { state }),
+        }
+    }
+
+    /// Pop the right-hand side of `p` off the stacks, and push its left-hand side.
+    fn reduce<'a>(
+        &self,
+        p: ProductionId,
+        states: &mut Vec<StateId>,
+        trees: &mut Vec<ParseTree<'a>>,
+    ) -> Result<(), ParseError<'a>> {
+        let state = *states.last().unwrap();
+        let production = self.table.grammar().production(p);
+        let n = production.rhs.len();
+        if n >= states.len() {
+            return Err(ParseError::BadTable { state });
+        }
+
+        states.truncate(states.len() - n);
+        let children = trees.split_off(trees.len() - n);
+        let next = (self.table)
+            .goto(*states.last().unwrap(), production.lhs)
+            .ok_or(ParseError::BadTable { state })?;
+        states.push(next);
+        trees.push(ParseTree::Node {
+            production: p,
+            children,
+        });
+        Ok(())
+    }
+}
+
+impl<'a> ParseError<'a> {
+    /// Where the error is: the unexpected token, or just past the last token. `None` if there's
+    /// nothing to point at.
+    pub fn start(&self) -> Option<Position<'a>> {
+        match self {
+            ParseError::UnexpectedToken { token, .. } => Some(token.start),
+            ParseError::UnexpectedEnd { last, .. } => {
+                let last = last.as_ref()?;
+                Some(Position {
+                    col: last.end_col(),
+                    ..last.start
+                })
+            }
+            ParseError::BadTable { .. } => None,
+        }
+    }
+
+    /// Describe the error to the user, pointing at the offending input. `None` if there's nothing
+    /// to point at, e.g. for an empty file.
+    pub fn diagnostic(&self) -> Option<Diagnostic<'a>> {
+        let start = self.start()?;
+        let message = self.to_string();
+        Some(match self {
+            ParseError::UnexpectedToken { token, .. } => {
+                let last = token.lexeme.char_indices().last().map_or(0, |(i, _)| i);
+                let end = Position {
+                    col: start.col + last,
+                    ..start
+                };
+                Diagnostic::error(message, start, end).with_label("unexpected token")
+            }
+            _ => Diagnostic::error(message, start, start).with_label("the file ends here"),
+        })
+    }
+}
+
+impl fmt::Display for ParseError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (found, expected) = match self {
+            ParseError::UnexpectedToken { token, expected } => {
+                (format!("`{}`", token.lexeme), expected)
+            }
+            ParseError::UnexpectedEnd { expected, .. } => (String::from("end of file"), expected),
+            ParseError::BadTable { state } => {
+                return write!(f, "the parse table got stuck in state {}", state.index());
+            }
+        };
+
+        let mut expected: Vec<_> = expected.iter().map(|&t| describe(t)).collect();
+        match expected.len() {
+            0 => write!(f, "unexpected {}", found),
+            1 => write!(f, "expected {}, found {}", expected[0], found),
+            _ => {
+                let last = expected.pop().unwrap();
+                let list = expected.join(", ");
+                write!(f, "expected one of {} or {}, found {}", list, last, found)
+            }
+        }
+    }
+}
+
+/// A terminal the way a user would think of it, e.g. `;` or "identifier".
+fn describe(t: Terminal) -> String {
+    let kind = match t {
+        Terminal::Bof => return String::from("start of file"),
+        Terminal::Eof => return String::from("end of file"),
+        Terminal::Token(kind) => kind,
+    };
+    let s = match kind {
+        TokenKind::Keyword(k) => return format!("`{}`", k),
+        TokenKind::Separator(s) => return format!("`{}`", s),
+        TokenKind::Operator(o) => return format!("`{}`", o),
+        TokenKind::Identifier => "identifier",
+        TokenKind::IntLiteral => "int literal",
+        TokenKind::HexIntLiteral => "hex literal",
+        TokenKind::OctalIntLiteral => "octal literal",
+        TokenKind::LongLiteral => "long literal",
+        TokenKind::FloatLiteral => "float literal",
+        TokenKind::DoubleLiteral => "double literal",
+        TokenKind::BoolLiteral => "`true` or `false`",
+        TokenKind::CharLiteral => "char literal",
+        TokenKind::StringLiteral => "string literal",
+        TokenKind::NullLiteral => "`null`",
+    };
+    String::from(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::ColorMode;
+    use crate::source_map::SourceMap;
+    use crate::test_util::{method, Rng};
+    use crate::tokenizer::{Text, Tokenizer};
+
+    const CFG: &str = "\
+7
+BOF
+EOF
+ID
+PLUS
+STAR
+LPAREN
+RPAREN
+4
+S
+expr
+term
+factor
+S
+7
+S BOF expr EOF
+expr expr PLUS term
+expr term
+term term STAR factor
+term factor
+factor ID
+factor LPAREN expr RPAREN
+";
+
+    fn parser() -> Parser {
+        Parser::new(ParseTable::build(Grammar::parse(CFG).unwrap()).unwrap())
+    }
+
+    fn parse<'a>(parser: &Parser, src: &'a str) -> Result<ParseTree<'a>, ParseError<'a>> {
+        parser.parse(Tokenizer::global().tokenize(Text(src)).map(Result::unwrap))
+    }
+
+    #[test]
+    fn trees() {
+        let parser = parser();
+        let grammar = parser.table().grammar();
+
+        let src = "a + b * (c)";
+        let tree = parse(&parser, src).unwrap();
+        assert_eq!(
+            "(S BOF (expr (expr (term (factor a))) + (term (term (factor b)) * (factor ( (expr (term (factor c))) )))) EOF)",
+            tree.sexp(grammar)
+        );
+
+        // The leaves are the original tokens.
+        let tokens = tree.tokens();
+        let lexemes: Vec<_> = tokens.iter().map(|t| t.lexeme).collect();
+        assert_eq!(vec!["a", "+", "b", "*", "(", "c", ")"], lexemes);
+        assert_eq!(6, tokens[3].start.col);
+        assert_eq!(src.len(), tree.span().unwrap().hi as usize);
+
+        // Each node has a child per symbol of its production.
+        if let ParseTree::Node {
+            production,
+            children,
+        } = &tree
+        {
+            assert_eq!(3, children.len());
+            assert_eq!(ProductionId(0), *production);
+        } else {
+            panic!("{:?}", tree);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let parser = parser();
+        for (src, expected) in [
+            ("a + + b", "expected one of identifier or `(`, found `+`"),
+            (
+                "a b",
+                "expected one of end of file, `)`, `+` or `*`, found `b`",
+            ),
+            ("(a", "expected one of `)` or `+`, found end of file"),
+            ("", "expected one of identifier or `(`, found end of file"),
+            (
+                "a;",
+                "expected one of end of file, `)`, `+` or `*`, found `;`",
+            ),
+        ] {
+            let err = parse(&parser, src).unwrap_err();
+            assert_eq!(expected, err.to_string(), "{:?}", src);
+        }
+
+        let mut map = SourceMap::new();
+        let file = map.add_file("Foo.java", "x = (a\n  + b");
+        let tokens = Tokenizer::global()
+            .tokenize(map.file(file))
+            .map(Result::unwrap);
+        let err = parser
+            .parse(tokens.skip(2).collect::<Vec<_>>())
+            .unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedEnd { .. }));
+        assert_eq!(
+            "\
+error: expected one of `)` or `+`, found end of file
+ --> Foo.java:2:6
+  |
+2 |   + b
+  |      ^ the file ends here
+",
+            map.render(&err.diagnostic().unwrap(), ColorMode::Plain)
+        );
+        assert_eq!(None, parse(&parser, "").unwrap_err().diagnostic());
+    }
+
+    /// The parser works the same with a table from a `.lr1` file.
+    #[test]
+    fn lr1_table() {
+        let ours = parser();
+        let theirs = Parser::new(ParseTable::from_lr1(&ours.table().to_lr1()).unwrap());
+        for src in ["a", "a * (b + c) * d", "a + (b"] {
+            assert_eq!(parse(&ours, src), parse(&theirs, src), "{:?}", src);
+        }
+    }
+
+    #[test]
+    fn bad_table() {
+        // Drop the gotos after `BOF`.
+        let mut table = parser().table().clone();
+        table.gotos[1].clear();
+        let parser = Parser::new(table);
+        assert_eq!(
+            Err(ParseError::BadTable { state: StateId(2) }),
+            parse(&parser, "a")
+        );
+    }
+
+    /// The first node for `nonterminal` in the tree, if any.
+    fn find<'t, 'a>(tree: &'t ParseTree<'a>, nonterminal: &str) -> Option<&'t ParseTree<'a>> {
+        let grammar = Parser::global().table().grammar();
+        let mut stack = vec![tree];
+        while let Some(tree) = stack.pop() {
+            if let ParseTree::Node { children, .. } = tree {
+                if grammar.name(tree.symbol(grammar)) == nonterminal {
+                    return Some(tree);
+                }
+                stack.extend(children.iter().rev());
+            }
+        }
+        None
+    }
+
+    /// The tree with its structure in brackets, but without labels or single-child nodes: e.g.
+    /// `(a + (b * c))`.
+    fn bracketed(tree: &ParseTree) -> String {
+        match tree {
+            ParseTree::Node { children, .. } if children.len() == 1 => bracketed(&children[0]),
+            ParseTree::Node { children, .. } => {
+                let children: Vec<_> = children.iter().map(bracketed).collect();
+                format!("({})", children.join(" "))
+            }
+            _ => tree.sexp(Parser::global().table().grammar()),
+        }
+    }
+
+    #[test]
+    fn joos_1w_statements() {
+        let parser = Parser::global();
+
+        // `else` goes with the closest `if`.
+        let src = method("if (a) if (b) x = 1; else x = 2;");
+        let tree = parse(parser, &src).unwrap();
+        let outer = find(&tree, "statement").unwrap();
+        assert!(find(outer, "if_then_statement").is_some());
+        let inner = find(outer, "if_then_else_statement").unwrap();
+        assert_eq!("(if ( b ) ((x = 1) ;) else ((x = 2) ;))", bracketed(inner));
+
+        for body in [
+            "int x = 1; x = x + 1; return;",
+            "for (;;) ;",
+            "for (int i = 0; i < n; i = i + 1) { a[i] = i; }",
+            "while (x > 0) if (y) x = x - 1; else return;",
+            "new A().x = 42; this.m(); a.b.c(d, e); new A();",
+            "int[] a = new int[5]; A[] b = new A[a.length]; java.util.Vector v = null;",
+            "{ { ; } }",
+            "throw new Exception();",
+        ] {
+            let src = method(body);
+            assert!(parse(parser, &src).is_ok(), "{:?}", body);
+        }
+
+        // Statements that Java has, but Joos doesn't, or not like this.
+        for body in [
+            "x + 1;",
+            "int x;",
+            "int x = 1, y = 2;",
+            "m() = 3;",
+            "x++;",
+            "x += 1;",
+            "do x = 1; while (true);",
+            "new int[1][2];",
+            "int[][] x = null;",
+            "super();",
+        ] {
+            let src = method(body);
+            assert!(parse(parser, &src).is_err(), "{:?}", body);
+        }
+    }
+
+    #[test]
+    fn joos_1w_expressions() {
+        let parser = Parser::global();
+        for (expr, expected) in [
+            (
+                "a || b && c | d & e == f",
+                "(a || (b && (c | (d & (e == f)))))",
+            ),
+            ("a == b < c + d * e", "(a == (b < (c + (d * e))))"),
+            ("a - b - c", "((a - b) - c)"),
+            ("-a * !b", "((- a) * (! b))"),
+            ("x instanceof A[] == y", "((x instanceof (A [ ])) == y)"),
+            ("a.b.c(d)[e].f", "(((((a . b) . c) ( d )) [ e ]) . f)"),
+            ("new A().b(c, d)", "((new A ( () )) . b ( (c , d) ))"),
+            ("y = z = 1", "(y = (z = 1))"),
+        ] {
+            let src = method(&format!("x = {};", expr));
+            let tree = parse(parser, &src).unwrap();
+            let assignment = find(&tree, "assignment").unwrap();
+            let expected = format!("(x = {})", expected);
+            assert_eq!(expected, bracketed(assignment), "{:?}", expr);
+        }
+
+        // Casts. A parenthesized name followed by `-` is a subtraction, not a cast.
+        for (expr, cast) in [
+            ("(A) x", true),
+            ("(int) -x", true),
+            ("(a) - x", false),
+            ("(A[]) x", true),
+            ("(int[]) (x)", true),
+            ("(a.b) !x", true),
+            ("(a + b) * c", false),
+        ] {
+            let src = method(&format!("x = {};", expr));
+            let tree = parse(parser, &src).unwrap();
+            assert_eq!(cast, find(&tree, "cast_expression").is_some(), "{:?}", expr);
+        }
+    }
+
+    /// Random token sequences are rejected without panicking, even with a broken table.
+    #[test]
+    fn random_input() {
+        let parser = parser();
+        let tokenizer = Tokenizer::new();
+        let words = ["a", "+", "*", "(", ")", ";"];
+        let mut rng = Rng::new(777);
+
+        let mut broken = parser.table().clone();
+        broken.gotos[0].clear();
+        broken.actions[3].clear();
+        let broken = Parser::new(broken);
+
+        for _ in 0..1000 {
+            let len = rng.below(10);
+            let src: Vec<_> = (0..len).map(|_| words[rng.below(words.len())]).collect();
+            let src = src.join(" ");
+            let tokens: Vec<_> = tokenizer.tokenize(Text(&src)).map(Result::unwrap).collect();
+            if let Ok(tree) = parser.parse(tokens.clone()) {
+                assert_eq!(tokens.len(), tree.tokens().len());
+            }
+            let _ = broken.parse(tokens);
+        }
+    }
+}
@@ -0,0 +1,481 @@
+//! Context-free grammars, in the course's `.cfg` format.
+//!
+//! A grammar file lists the terminals, the nonterminals, the start symbol, and the productions,
+//! each section preceded by its length:
+//! ```text
+//! 4
+//! BOF
+//! EOF
+//! ID
+//! PLUS
+//! 2
+//! S
+//! expr
+//! S
+//! 3
+//! S BOF expr EOF
+//! expr expr PLUS ID
+//! expr ID
+//! ```
+//! A production is its left-hand side, followed by the symbols of its right-hand side (if any),
+//! separated by whitespace.
+//!
+//! Terminals are named by `TokenKind::name`, plus `BOF` and `EOF`, which mark the start and end of
+//! the input. The start symbol may not appear on the right-hand side of any production; normally
+//! it has a single production like `S BOF expr EOF` above.
+
+use crate::tokenizer::token_types::TokenKind;
+use std::collections::HashMap as Map;
+use std::fmt;
+
+/// A terminal symbol of a grammar: a kind of token, or an end marker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Terminal {
+    /// Beginning of file.
+    Bof,
+    /// End of file.
+    Eof,
+    Token(TokenKind),
+}
+
+impl Terminal {
+    pub fn name(self) -> &'static str {
+        match self {
+            Terminal::Bof => "BOF",
+            Terminal::Eof => "EOF",
+            Terminal::Token(kind) => kind.name(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "BOF" => Some(Terminal::Bof),
+            "EOF" => Some(Terminal::Eof),
+            _ => TokenKind::from_name(name).map(Terminal::Token),
+        }
+    }
+}
+
+impl fmt::Display for Terminal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Identifies a nonterminal by its position in the grammar file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Nonterminal(pub(super) u32);
+
+impl Nonterminal {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Symbol {
+    Terminal(Terminal),
+    Nonterminal(Nonterminal),
+}
+
+/// Identifies a production by its position in the grammar file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProductionId(pub(super) u32);
+
+impl ProductionId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Production {
+    pub lhs: Nonterminal,
+    pub rhs: Vec<Symbol>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    /// In the order of the grammar file.
+    terminals: Vec<Terminal>,
+    nonterminals: Vec<String>,
+    start: Nonterminal,
+    productions: Vec<Production>,
+    /// The position of each terminal in `terminals`.
+    terminal_indices: Map<Terminal, usize>,
+    /// The productions of each nonterminal, indexed by `Nonterminal::index`.
+    productions_of: Vec<Vec<ProductionId>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrammarError {
+    /// The line of the grammar file with the error. One-indexed. `None` if the file ended early.
+    pub line: Option<usize>,
+    pub kind: GrammarErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrammarErrorKind {
+    /// A line that should have been the length of the next section.
+    ExpectedCount,
+    /// A line that should have been a single symbol.
+    ExpectedSymbol,
+    UnknownTerminal(String),
+    UnknownSymbol(String),
+    DuplicateSymbol(String),
+    /// The start symbol isn't a nonterminal.
+    BadStart(String),
+    /// A production whose left-hand side isn't a nonterminal.
+    BadLhs(String),
+    /// The start symbol appears on the right-hand side of a production.
+    StartInRhs,
+    NoStartProduction,
+    /// There's more after the last production.
+    TrailingInput,
+}
+
+impl fmt::Display for GrammarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: ", line)?,
+            None => write!(f, "end of file: ")?,
+        }
+        match &self.kind {
+            GrammarErrorKind::ExpectedCount => write!(f, "expected a count"),
+            GrammarErrorKind::ExpectedSymbol => write!(f, "expected a single symbol"),
+            GrammarErrorKind::UnknownTerminal(name) => write!(f, "unknown terminal `{}`", name),
+            GrammarErrorKind::UnknownSymbol(name) => write!(f, "unknown symbol `{}`", name),
+            GrammarErrorKind::DuplicateSymbol(name) => write!(f, "duplicate symbol `{}`", name),
+            GrammarErrorKind::BadStart(name) => {
+                write!(f, "start symbol `{}` isn't a nonterminal", name)
+            }
+            GrammarErrorKind::BadLhs(name) => write!(f, "`{}` isn't a nonterminal", name),
+            GrammarErrorKind::StartInRhs => {
+                write!(f, "the start symbol can't be on the right-hand side")
+            }
+            GrammarErrorKind::NoStartProduction => {
+                write!(f, "there's no production for the start symbol")
+            }
+            GrammarErrorKind::TrailingInput => write!(f, "expected end of file"),
+        }
+    }
+}
+
+impl Grammar {
+    /// Parse a grammar in the `.cfg` format.
+    pub fn parse(text: &str) -> Result<Self, GrammarError> {
+        let mut lines = text.lines().enumerate();
+        let grammar = Self::read(&mut lines)?;
+        match lines.find(|(_, line)| !line.trim().is_empty()) {
+            Some((i, _)) => Err(GrammarError {
+                line: Some(i + 1),
+                kind: GrammarErrorKind::TrailingInput,
+            }),
+            None => Ok(grammar),
+        }
+    }
+
+    /// Read a grammar from the start of `lines`, leaving the rest. Lines are numbered from zero.
+    pub(super) fn read<'a>(
+        lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    ) -> Result<Self, GrammarError> {
+        let mut reader = Reader { lines, line: None };
+
+        let mut terminals = vec![];
+        let mut terminal_indices = Map::new();
+        for _ in 0..reader.count()? {
+            let name = reader.symbol()?;
+            let t = Terminal::from_name(name)
+                .ok_or_else(|| reader.error(GrammarErrorKind::UnknownTerminal(name.into())))?;
+            if terminal_indices.insert(t, terminals.len()).is_some() {
+                return Err(reader.error(GrammarErrorKind::DuplicateSymbol(name.into())));
+            }
+            terminals.push(t);
+        }
+
+        let mut nonterminals = vec![];
+        let mut nonterminal_ids = Map::new();
+        for i in 0..reader.count()? {
+            let name = reader.symbol()?;
+            let is_terminal = terminals.iter().any(|t| t.name() == name);
+            if is_terminal
+                || nonterminal_ids
+                    .insert(name, Nonterminal(i as u32))
+                    .is_some()
+            {
+                return Err(reader.error(GrammarErrorKind::DuplicateSymbol(name.into())));
+            }
+            nonterminals.push(String::from(name));
+        }
+
+        let name = reader.symbol()?;
+        let start = *nonterminal_ids
+            .get(name)
+            .ok_or_else(|| reader.error(GrammarErrorKind::BadStart(name.into())))?;
+
+        let mut productions = vec![];
+        let mut productions_of = vec![vec![]; nonterminals.len()];
+        for _ in 0..reader.count()? {
+            let mut words = reader.next()?.split_whitespace();
+            let name = words
+                .next()
+                .ok_or_else(|| reader.error(GrammarErrorKind::ExpectedSymbol))?;
+            let lhs = *nonterminal_ids
+                .get(name)
+                .ok_or_else(|| reader.error(GrammarErrorKind::BadLhs(name.into())))?;
+
+            let mut rhs = vec![];
+            for name in words {
+                let symbol = if let Some(&nt) = nonterminal_ids.get(name) {
+                    Symbol::Nonterminal(nt)
+                } else {
+                    match Terminal::from_name(name) {
+                        Some(t) if terminal_indices.contains_key(&t) => Symbol::Terminal(t),
+                        _ => return Err(reader.error(GrammarErrorKind::UnknownSymbol(name.into()))),
+                    }
+                };
+                if symbol == Symbol::Nonterminal(start) {
+                    return Err(reader.error(GrammarErrorKind::StartInRhs));
+                }
+                rhs.push(symbol);
+            }
+
+            productions_of[lhs.index()].push(ProductionId(productions.len() as u32));
+            productions.push(Production { lhs, rhs });
+        }
+
+        if productions_of[start.index()].is_empty() {
+            return Err(reader.error(GrammarErrorKind::NoStartProduction));
+        }
+
+        Ok(Self {
+            terminals,
+            nonterminals,
+            start,
+            productions,
+            terminal_indices,
+            productions_of,
+        })
+    }
+
+    /// Write the grammar in the `.cfg` format.
+    pub fn to_cfg(&self) -> String {
+        let mut out = String::new();
+        out += &format!("{}\n", self.terminals.len());
+        for t in &self.terminals {
+            out += &format!("{}\n", t);
+        }
+        out += &format!("{}\n", self.nonterminals.len());
+        for nt in &self.nonterminals {
+            out += &format!("{}\n", nt);
+        }
+        out += &format!("{}\n", self.name(Symbol::Nonterminal(self.start)));
+        out += &format!("{}\n", self.productions.len());
+        for p in &self.productions {
+            let mut words = vec![self.name(Symbol::Nonterminal(p.lhs))];
+            words.extend(p.rhs.iter().map(|&s| self.name(s)));
+            out += &format!("{}\n", words.join(" "));
+        }
+        out
+    }
+
+    pub fn terminals(&self) -> &[Terminal] {
+        &self.terminals
+    }
+
+    /// The position of `t` in `terminals`, if it's part of the grammar.
+    pub fn terminal_index(&self, t: Terminal) -> Option<usize> {
+        self.terminal_indices.get(&t).copied()
+    }
+
+    pub fn num_nonterminals(&self) -> usize {
+        self.nonterminals.len()
+    }
+
+    pub fn start(&self) -> Nonterminal {
+        self.start
+    }
+
+    pub fn productions(&self) -> &[Production] {
+        &self.productions
+    }
+
+    pub fn production(&self, id: ProductionId) -> &Production {
+        &self.productions[id.index()]
+    }
+
+    pub fn productions_of(&self, nt: Nonterminal) -> &[ProductionId] {
+        &self.productions_of[nt.index()]
+    }
+
+    pub fn nonterminal(&self, name: &str) -> Option<Nonterminal> {
+        let i = self.nonterminals.iter().position(|n| n == name)?;
+        Some(Nonterminal(i as u32))
+    }
+
+    pub fn name(&self, symbol: Symbol) -> &str {
+        match symbol {
+            Symbol::Terminal(t) => t.name(),
+            Symbol::Nonterminal(nt) => &self.nonterminals[nt.index()],
+        }
+    }
+
+    /// E.g. `expr -> expr PLUS ID`.
+    pub fn display_production(&self, id: ProductionId) -> String {
+        self.display_item(id, None)
+    }
+
+    /// A production with a dot before the symbol at `dot`, e.g. `expr -> expr . PLUS ID`.
+    pub(super) fn display_item(&self, id: ProductionId, dot: Option<usize>) -> String {
+        let p = self.production(id);
+        let mut words = vec![self.name(Symbol::Nonterminal(p.lhs)), "->"];
+        for (i, &s) in p.rhs.iter().enumerate() {
+            if dot == Some(i) {
+                words.push(".");
+            }
+            words.push(self.name(s));
+        }
+        if dot == Some(p.rhs.len()) {
+            words.push(".");
+        }
+        words.join(" ")
+    }
+}
+
+/// Reads a grammar file line by line, remembering where it is for errors.
+struct Reader<'i, I> {
+    lines: &'i mut I,
+    /// The zero-indexed number of the last line read.
+    line: Option<usize>,
+}
+
+impl<'a, I: Iterator<Item = (usize, &'a str)>> Reader<'_, I> {
+    fn next(&mut self) -> Result<&'a str, GrammarError> {
+        match self.lines.next() {
+            Some((i, line)) => {
+                self.line = Some(i);
+                Ok(line)
+            }
+            None => Err(GrammarError {
+                line: None,
+                kind: GrammarErrorKind::ExpectedSymbol,
+            }),
+        }
+    }
+
+    fn count(&mut self) -> Result<usize, GrammarError> {
+        let line = self.next().map_err(|e| GrammarError {
+            kind: GrammarErrorKind::ExpectedCount,
+            ..e
+        })?;
+        line.trim()
+            .parse()
+            .map_err(|_| self.error(GrammarErrorKind::ExpectedCount))
+    }
+
+    fn symbol(&mut self) -> Result<&'a str, GrammarError> {
+        let line = self.next()?.trim();
+        if line.is_empty() || line.contains(char::is_whitespace) {
+            return Err(self.error(GrammarErrorKind::ExpectedSymbol));
+        }
+        Ok(line)
+    }
+
+    /// An error on the last line read.
+    fn error(&self, kind: GrammarErrorKind) -> GrammarError {
+        GrammarError {
+            line: self.line.map(|i| i + 1),
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CFG: &str = "\
+4
+BOF
+EOF
+ID
+PLUS
+2
+S
+expr
+S
+3
+S BOF expr EOF
+expr expr PLUS ID
+expr ID
+";
+
+    #[test]
+    fn parse() {
+        let g = Grammar::parse(CFG).unwrap();
+        assert_eq!(4, g.terminals().len());
+        assert_eq!(2, g.num_nonterminals());
+        assert_eq!(Some(g.start()), g.nonterminal("S"));
+
+        let expr = g.nonterminal("expr").unwrap();
+        assert_eq!(&[ProductionId(1), ProductionId(2)], g.productions_of(expr));
+        assert_eq!(
+            "expr -> expr PLUS ID",
+            g.display_production(ProductionId(1))
+        );
+        assert_eq!(
+            "S -> BOF expr . EOF",
+            g.display_item(ProductionId(0), Some(2))
+        );
+        assert_eq!(
+            Some(Terminal::Token(TokenKind::Identifier)),
+            Terminal::from_name("ID")
+        );
+
+        assert_eq!(CFG, g.to_cfg());
+        assert_eq!(g, Grammar::parse(&g.to_cfg()).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        let replace = |from, to| CFG.replacen(from, to, 1);
+        for (cfg, expected) in [
+            (replace("4\n", "four\n"), "line 1: expected a count"),
+            (
+                replace("PLUS", "PLUS MINUS"),
+                "line 5: expected a single symbol",
+            ),
+            (replace("PLUS", "+"), "line 5: unknown terminal `+`"),
+            (replace("PLUS", "ID"), "line 5: duplicate symbol `ID`"),
+            (replace("expr\n", "ID\n"), "line 8: duplicate symbol `ID`"),
+            (
+                replace("S\n3", "ID\n3"),
+                "line 9: start symbol `ID` isn't a nonterminal",
+            ),
+            (
+                replace("expr expr PLUS ID", "expr expr MINUS ID"),
+                "line 12: unknown symbol `MINUS`",
+            ),
+            (
+                replace("expr ID\n", "ID expr\n"),
+                "line 13: `ID` isn't a nonterminal",
+            ),
+            (
+                replace("expr ID\n", "expr S\n"),
+                "line 13: the start symbol can't be on the right-hand side",
+            ),
+            (
+                replace("S BOF expr EOF", "expr BOF expr EOF"),
+                "line 13: there's no production for the start symbol",
+            ),
+            (
+                replace("3\nS", "4\nS"),
+                "end of file: expected a single symbol",
+            ),
+            (replace("3\nS", "2\nS"), "line 13: expected end of file"),
+        ] {
+            let actual = Grammar::parse(&cfg).unwrap_err().to_string();
+            assert_eq!(expected, actual, "{}", cfg);
+        }
+    }
+}
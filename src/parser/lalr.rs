@@ -0,0 +1,681 @@
+//! LALR(1) parse tables.
+//!
+//! The table is built the usual way (see the Dragon Book, section 4.7):
+//! 1. Build the LR(0) automaton, whose states are sets of items like `expr -> expr . PLUS ID`.
+//! 2. Find the lookaheads of each state's kernel items, by working out which lookaheads each item
+//!    generates "spontaneously", and which it passes on to items in other states; then passing
+//!    them on until nothing changes.
+//! 3. Reduce by a completed item on each of its lookaheads, and shift on every terminal after a
+//!    dot.
+//!
+//! If a state has more than one action for a terminal, the grammar isn't LALR(1), and we report
+//! each such `Conflict`.
+
+use crate::parser::grammar::{Grammar, Nonterminal, ProductionId, Symbol, Terminal};
+use std::collections::{BTreeMap, HashMap as Map, VecDeque};
+use std::fmt;
+
+/// Identifies a state of the parser. The initial state is 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StateId(pub(super) u32);
+
+impl StateId {
+    pub const INIT: StateId = StateId(0);
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    Shift(StateId),
+    Reduce(ProductionId),
+}
+
+/// An action/goto table for a `Grammar`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTable {
+    pub(super) grammar: Grammar,
+    /// Indexed by `StateId::index`.
+    pub(super) actions: Vec<Map<Terminal, Action>>,
+    /// Indexed by `StateId::index`.
+    pub(super) gotos: Vec<Map<Nonterminal, StateId>>,
+}
+
+/// More than one action for the same state and lookahead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub state: StateId,
+    pub terminal: Terminal,
+    /// Is one of the actions a shift? (Otherwise, they're all reduces.)
+    pub shift: bool,
+    /// The items behind the conflicting actions, like `expr -> expr . PLUS ID`.
+    pub items: Vec<String>,
+    /// The shortest sequence of symbols that leads to the state, like `BOF expr`.
+    pub prefix: String,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.shift { "shift" } else { "reduce" };
+        writeln!(
+            f,
+            "{}/reduce conflict in state {} on {}, after `{}`:",
+            kind, self.state.0, self.terminal, self.prefix
+        )?;
+        for item in &self.items {
+            writeln!(f, "    {}", item)?;
+        }
+        Ok(())
+    }
+}
+
+impl ParseTable {
+    /// Build the LALR(1) table for `grammar`, or report every conflict.
+    pub fn build(grammar: Grammar) -> Result<Self, Vec<Conflict>> {
+        Builder::new(&grammar).build().map(|(actions, gotos)| Self {
+            grammar,
+            actions,
+            gotos,
+        })
+    }
+
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn action(&self, state: StateId, t: Terminal) -> Option<Action> {
+        self.actions.get(state.index())?.get(&t).copied()
+    }
+
+    pub fn goto(&self, state: StateId, nt: Nonterminal) -> Option<StateId> {
+        self.gotos.get(state.index())?.get(&nt).copied()
+    }
+
+    /// The terminals with an action in `state`, in order.
+    pub fn expected(&self, state: StateId) -> Vec<Terminal> {
+        let mut expected: Vec<_> = self
+            .actions
+            .get(state.index())
+            .into_iter()
+            .flat_map(|row| row.keys().copied())
+            .collect();
+        expected.sort();
+        expected
+    }
+}
+
+/// An LR(0) item: a production, with a dot before the symbol at `dot`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Item {
+    production: ProductionId,
+    dot: u32,
+}
+
+/// A set of terminals, by their index in the grammar. The extra bit at `Grammar::terminals().len()`
+/// stands for the dummy lookahead `#` used to find propagated lookaheads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TerminalSet {
+    bits: Vec<u64>,
+}
+
+impl TerminalSet {
+    fn new(num_terminals: usize) -> Self {
+        Self {
+            bits: vec![0; num_terminals / 64 + 1],
+        }
+    }
+
+    fn insert(&mut self, i: usize) -> bool {
+        let (word, bit) = (i / 64, 1 << (i % 64));
+        let changed = self.bits[word] & bit == 0;
+        self.bits[word] |= bit;
+        changed
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        self.bits[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn remove(&mut self, i: usize) {
+        self.bits[i / 64] &= !(1 << (i % 64));
+    }
+
+    /// Add the elements of `other`. Returns whether anything was added.
+    fn union_with(&mut self, other: &TerminalSet) -> bool {
+        let mut changed = false;
+        for (a, b) in self.bits.iter_mut().zip(&other.bits) {
+            changed |= *b & !*a != 0;
+            *a |= b;
+        }
+        changed
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        let len = self.bits.len() * 64;
+        (0..len).filter(move |&i| self.contains(i))
+    }
+}
+
+/// The actions and the gotos of each state.
+type Rows = (Vec<Map<Terminal, Action>>, Vec<Map<Nonterminal, StateId>>);
+
+struct Builder<'g> {
+    grammar: &'g Grammar,
+    /// The index of the dummy lookahead `#`.
+    dummy: usize,
+    nullable: Vec<bool>,
+    /// Indexed by `Nonterminal::index`.
+    first: Vec<TerminalSet>,
+    /// The kernel of each LR(0) state, sorted.
+    kernels: Vec<Vec<Item>>,
+    /// The transitions of each LR(0) state.
+    transitions: Vec<BTreeMap<Symbol, StateId>>,
+}
+
+impl<'g> Builder<'g> {
+    fn new(grammar: &'g Grammar) -> Self {
+        let mut builder = Self {
+            grammar,
+            dummy: grammar.terminals().len(),
+            nullable: vec![],
+            first: vec![],
+            kernels: vec![],
+            transitions: vec![],
+        };
+        builder.compute_first();
+        builder.compute_lr0();
+        builder
+    }
+
+    fn empty_set(&self) -> TerminalSet {
+        TerminalSet::new(self.dummy + 1)
+    }
+
+    fn terminal_index(&self, t: Terminal) -> usize {
+        // Productions only mention terminals of the grammar.
+        self.grammar.terminal_index(t).unwrap()
+    }
+
+    /// Find which nonterminals derive the empty string, and the terminals each can start with.
+    fn compute_first(&mut self) {
+        let n = self.grammar.num_nonterminals();
+        self.nullable = vec![false; n];
+        self.first = vec![self.empty_set(); n];
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for p in self.grammar.productions() {
+                let lhs = p.lhs.index();
+                let (first, nullable) = self.first_of(&p.rhs);
+                changed |= self.first[lhs].union_with(&first);
+                if nullable && !self.nullable[lhs] {
+                    self.nullable[lhs] = true;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    /// The terminals that `symbols` can start with, and whether it can derive the empty string.
+    fn first_of(&self, symbols: &[Symbol]) -> (TerminalSet, bool) {
+        let mut first = self.empty_set();
+        for &s in symbols {
+            match s {
+                Symbol::Terminal(t) => {
+                    first.insert(self.terminal_index(t));
+                    return (first, false);
+                }
+                Symbol::Nonterminal(nt) => {
+                    first.union_with(&self.first[nt.index()]);
+                    if !self.nullable[nt.index()] {
+                        return (first, false);
+                    }
+                }
+            }
+        }
+        (first, true)
+    }
+
+    /// The symbol after the dot, if any.
+    fn next_symbol(&self, item: Item) -> Option<Symbol> {
+        let rhs = &self.grammar.production(item.production).rhs;
+        rhs.get(item.dot as usize).copied()
+    }
+
+    fn advance(item: Item) -> Item {
+        Item {
+            dot: item.dot + 1,
+            ..item
+        }
+    }
+
+    /// The LR(0) closure of a kernel.
+    fn closure0(&self, kernel: &[Item]) -> Vec<Item> {
+        let mut items = kernel.to_vec();
+        let mut added = vec![false; self.grammar.num_nonterminals()];
+        let mut i = 0;
+        while i < items.len() {
+            if let Some(Symbol::Nonterminal(nt)) = self.next_symbol(items[i]) {
+                if !added[nt.index()] {
+                    added[nt.index()] = true;
+                    items.extend(
+                        self.grammar
+                            .productions_of(nt)
+                            .iter()
+                            .map(|&production| Item { production, dot: 0 }),
+                    );
+                }
+            }
+            i += 1;
+        }
+        items
+    }
+
+    /// Build the LR(0) automaton, starting from the start symbol's productions.
+    fn compute_lr0(&mut self) {
+        let start = self.grammar.start();
+        let init: Vec<_> = (self.grammar.productions_of(start).iter())
+            .map(|&production| Item { production, dot: 0 })
+            .collect();
+
+        let mut ids = Map::new();
+        ids.insert(init.clone(), StateId::INIT);
+        self.kernels.push(init);
+
+        let mut i = 0;
+        while i < self.kernels.len() {
+            // Group the items by the symbol after the dot.
+            let mut gotos: BTreeMap<Symbol, Vec<Item>> = BTreeMap::new();
+            for item in self.closure0(&self.kernels[i]) {
+                if let Some(s) = self.next_symbol(item) {
+                    gotos.entry(s).or_default().push(Self::advance(item));
+                }
+            }
+
+            let mut transitions = BTreeMap::new();
+            for (s, mut kernel) in gotos {
+                kernel.sort();
+                kernel.dedup();
+                let next = *ids.entry(kernel.clone()).or_insert_with(|| {
+                    self.kernels.push(kernel);
+                    StateId(self.kernels.len() as u32 - 1)
+                });
+                transitions.insert(s, next);
+            }
+            self.transitions.push(transitions);
+            i += 1;
+        }
+    }
+
+    /// The LR(1) closure of some items with lookaheads. Items are kept in the order they're found.
+    fn closure1(&self, kernel: Vec<(Item, TerminalSet)>) -> Vec<(Item, TerminalSet)> {
+        let mut indices: Map<Item, usize> = Map::new();
+        for (i, (item, _)) in kernel.iter().enumerate() {
+            indices.insert(*item, i);
+        }
+        let mut items = kernel;
+        let mut queue: VecDeque<usize> = (0..items.len()).collect();
+        let mut queued = vec![true; items.len()];
+
+        while let Some(i) = queue.pop_front() {
+            queued[i] = false;
+            let (item, lookahead) = &items[i];
+            let nt = match self.next_symbol(*item) {
+                Some(Symbol::Nonterminal(nt)) => nt,
+                _ => continue,
+            };
+
+            // Items for `nt` get the lookaheads that can follow it here.
+            let rest = &self.grammar.production(item.production).rhs[item.dot as usize + 1..];
+            let (mut new_lookahead, nullable) = self.first_of(rest);
+            if nullable {
+                new_lookahead.union_with(lookahead);
+            }
+
+            for &production in self.grammar.productions_of(nt) {
+                let new_item = Item { production, dot: 0 };
+                let j = match indices.get(&new_item) {
+                    Some(&j) => {
+                        if !items[j].1.union_with(&new_lookahead) {
+                            continue;
+                        }
+                        j
+                    }
+                    None => {
+                        indices.insert(new_item, items.len());
+                        items.push((new_item, new_lookahead.clone()));
+                        queued.push(false);
+                        items.len() - 1
+                    }
+                };
+                if !queued[j] {
+                    queued[j] = true;
+                    queue.push_back(j);
+                }
+            }
+        }
+        items
+    }
+
+    /// Find the lookaheads of every kernel item, indexed like `kernels`.
+    fn compute_lookaheads(&self) -> Vec<Vec<TerminalSet>> {
+        let mut lookaheads: Vec<Vec<TerminalSet>> = (self.kernels.iter())
+            .map(|kernel| vec![self.empty_set(); kernel.len()])
+            .collect();
+        // Edges from each kernel item, to the kernel items it passes its lookaheads to.
+        let mut propagate: Map<(usize, usize), Vec<(usize, usize)>> = Map::new();
+
+        for (state, kernel) in self.kernels.iter().enumerate() {
+            for (k, &item) in kernel.iter().enumerate() {
+                let mut dummy = self.empty_set();
+                dummy.insert(self.dummy);
+
+                for (closed, lookahead) in self.closure1(vec![(item, dummy)]) {
+                    let s = match self.next_symbol(closed) {
+                        Some(s) => s,
+                        None => continue,
+                    };
+                    let target = self.transitions[state][&s].index();
+                    let advanced = Self::advance(closed);
+                    let j = self.kernels[target].binary_search(&advanced).unwrap();
+
+                    let mut spontaneous = lookahead.clone();
+                    spontaneous.remove(self.dummy);
+                    lookaheads[target][j].union_with(&spontaneous);
+                    if lookahead.contains(self.dummy) {
+                        propagate.entry((state, k)).or_default().push((target, j));
+                    }
+                }
+            }
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (&(state, k), targets) in &propagate {
+                for &(target, j) in targets {
+                    if (state, k) == (target, j) {
+                        continue;
+                    }
+                    let from = lookaheads[state][k].clone();
+                    changed |= lookaheads[target][j].union_with(&from);
+                }
+            }
+        }
+        lookaheads
+    }
+
+    /// For each state, the shortest sequence of symbols that reaches it from the initial state.
+    fn prefixes(&self) -> Vec<Vec<Symbol>> {
+        let mut prefixes = vec![None; self.kernels.len()];
+        prefixes[0] = Some(vec![]);
+        let mut queue = VecDeque::from(vec![0]);
+        while let Some(state) = queue.pop_front() {
+            for (&s, &next) in &self.transitions[state] {
+                if prefixes[next.index()].is_none() {
+                    let mut prefix = prefixes[state].clone().unwrap();
+                    prefix.push(s);
+                    prefixes[next.index()] = Some(prefix);
+                    queue.push_back(next.index());
+                }
+            }
+        }
+        prefixes.into_iter().map(Option::unwrap).collect()
+    }
+
+    fn build(&self) -> Result<Rows, Vec<Conflict>> {
+        let lookaheads = self.compute_lookaheads();
+        let terminals = self.grammar.terminals();
+
+        let mut actions = vec![];
+        let mut gotos = vec![];
+        let mut conflicts = vec![];
+        for (state, kernel) in self.kernels.iter().enumerate() {
+            let kernel = kernel
+                .iter()
+                .copied()
+                .zip(lookaheads[state].iter().cloned());
+
+            // Every candidate action, with the items behind it.
+            let mut candidates: BTreeMap<Terminal, BTreeMap<Action, Vec<Item>>> = BTreeMap::new();
+            let mut row_gotos = Map::new();
+            for (item, lookahead) in self.closure1(kernel.collect()) {
+                match self.next_symbol(item) {
+                    Some(Symbol::Terminal(t)) => {
+                        let next = self.transitions[state][&Symbol::Terminal(t)];
+                        let items = candidates.entry(t).or_default();
+                        items.entry(Action::Shift(next)).or_default().push(item);
+                    }
+                    Some(Symbol::Nonterminal(nt)) => {
+                        row_gotos.insert(nt, self.transitions[state][&Symbol::Nonterminal(nt)]);
+                    }
+                    None => {
+                        for i in lookahead.iter() {
+                            let items = candidates.entry(terminals[i]).or_default();
+                            let action = Action::Reduce(item.production);
+                            items.entry(action).or_default().push(item);
+                        }
+                    }
+                }
+            }
+
+            let mut row = Map::new();
+            for (t, options) in candidates {
+                if options.len() == 1 {
+                    row.insert(t, *options.keys().next().unwrap());
+                    continue;
+                }
+
+                let mut items: Vec<_> = options.values().flatten().copied().collect();
+                items.sort();
+                let items = items
+                    .into_iter()
+                    .map(|i| {
+                        self.grammar
+                            .display_item(i.production, Some(i.dot as usize))
+                    })
+                    .collect();
+                conflicts.push(Conflict {
+                    state: StateId(state as u32),
+                    terminal: t,
+                    shift: options.keys().any(|a| matches!(a, Action::Shift(_))),
+                    items,
+                    prefix: String::new(),
+                });
+            }
+            actions.push(row);
+            gotos.push(row_gotos);
+        }
+
+        if conflicts.is_empty() {
+            return Ok((actions, gotos));
+        }
+        let prefixes = self.prefixes();
+        for c in &mut conflicts {
+            let names: Vec<_> = (prefixes[c.state.index()].iter())
+                .map(|&s| self.grammar.name(s))
+                .collect();
+            c.prefix = names.join(" ");
+        }
+        Err(conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::token_types::{Operator, Separator, TokenKind};
+
+    /// Build a grammar from just its productions. The first production is for the start symbol.
+    fn grammar(productions: &[&str]) -> Grammar {
+        let mut terminals = vec![];
+        let mut nonterminals: Vec<&str> = vec![];
+        for p in productions {
+            let lhs = p.split_whitespace().next().unwrap();
+            if !nonterminals.contains(&lhs) {
+                nonterminals.push(lhs);
+            }
+        }
+        for p in productions {
+            for s in p.split_whitespace() {
+                if !nonterminals.contains(&s) && !terminals.contains(&s) {
+                    terminals.push(s);
+                }
+            }
+        }
+
+        let mut cfg = format!("{}\n{}\n", terminals.len(), terminals.join("\n"));
+        cfg += &format!("{}\n{}\n", nonterminals.len(), nonterminals.join("\n"));
+        cfg += &format!("{}\n", nonterminals[0]);
+        cfg += &format!("{}\n{}\n", productions.len(), productions.join("\n"));
+        Grammar::parse(&cfg).unwrap()
+    }
+
+    /// The actions for a sequence of terminals, starting in the initial state: the LR automaton,
+    /// without the stack. Just enough to check that a table accepts a sentence.
+    fn accepts(table: &ParseTable, input: &[&str]) -> bool {
+        let mut stack = vec![StateId::INIT];
+        for name in input {
+            let t = Terminal::from_name(name).unwrap();
+            loop {
+                match table.action(*stack.last().unwrap(), t) {
+                    Some(Action::Shift(next)) => {
+                        stack.push(next);
+                        break;
+                    }
+                    Some(Action::Reduce(p)) => {
+                        let p = table.grammar().production(p);
+                        stack.truncate(stack.len() - p.rhs.len());
+                        let goto = table.goto(*stack.last().unwrap(), p.lhs).unwrap();
+                        stack.push(goto);
+                    }
+                    None => return false,
+                }
+            }
+        }
+        // The start production is all that's left.
+        let start = table.grammar().productions_of(table.grammar().start());
+        (start.iter()).any(|&p| table.grammar().production(p).rhs.len() == stack.len() - 1)
+    }
+
+    #[test]
+    fn expressions() {
+        let g = grammar(&[
+            "S BOF expr EOF",
+            "expr expr PLUS term",
+            "expr term",
+            "term term STAR factor",
+            "term factor",
+            "factor ID",
+            "factor LPAREN expr RPAREN",
+        ]);
+        let table = ParseTable::build(g).unwrap();
+
+        for (input, ok) in [
+            ("BOF ID EOF", true),
+            ("BOF ID PLUS ID STAR ID EOF", true),
+            ("BOF LPAREN ID PLUS ID RPAREN STAR ID EOF", true),
+            ("BOF ID ID EOF", false),
+            ("BOF ID PLUS EOF", false),
+            ("BOF LPAREN ID EOF", false),
+        ] {
+            let input: Vec<_> = input.split(' ').collect();
+            assert_eq!(ok, accepts(&table, &input), "{:?}", input);
+        }
+
+        let plus = Terminal::Token(TokenKind::Operator(Operator::Plus));
+        let after_bof = table.action(StateId::INIT, Terminal::Bof);
+        let after_bof = match after_bof {
+            Some(Action::Shift(s)) => s,
+            a => panic!("{:?}", a),
+        };
+        assert_eq!(None, table.action(after_bof, plus));
+        assert_eq!(
+            vec![
+                Terminal::Token(TokenKind::Identifier),
+                Terminal::Token(TokenKind::Separator(Separator::LParen)),
+            ],
+            table.expected(after_bof)
+        );
+    }
+
+    /// This grammar is LALR(1), but not SLR(1): the lookaheads have to be more precise than
+    /// FOLLOW sets.
+    #[test]
+    fn lalr_not_slr() {
+        let g = grammar(&[
+            "S BOF s EOF",
+            "s l ASSIGN r",
+            "s r",
+            "l STAR r",
+            "l ID",
+            "r l",
+        ]);
+        let table = ParseTable::build(g).unwrap();
+        assert!(accepts(
+            &table,
+            &["BOF", "STAR", "ID", "ASSIGN", "ID", "EOF"]
+        ));
+        assert!(accepts(&table, &["BOF", "STAR", "STAR", "ID", "EOF"]));
+        assert!(!accepts(&table, &["BOF", "ID", "ASSIGN", "EOF"]));
+    }
+
+    /// Lookaheads have to pass through nullable nonterminals.
+    #[test]
+    fn nullable() {
+        let g = grammar(&[
+            "S BOF list EOF",
+            "list",
+            "list list item",
+            "item ID opt",
+            "opt",
+            "opt SEMICOLON",
+        ]);
+        let table = ParseTable::build(g).unwrap();
+        assert!(accepts(&table, &["BOF", "EOF"]));
+        assert!(accepts(
+            &table,
+            &["BOF", "ID", "ID", "SEMICOLON", "ID", "EOF"]
+        ));
+        assert!(!accepts(&table, &["BOF", "SEMICOLON", "EOF"]));
+    }
+
+    #[test]
+    fn conflicts() {
+        let g = grammar(&[
+            "S BOF stmt EOF",
+            "stmt IF stmt",
+            "stmt IF stmt ELSE stmt",
+            "stmt ID",
+        ]);
+        let conflicts = ParseTable::build(g).unwrap_err();
+        assert_eq!(1, conflicts.len());
+        assert_eq!(
+            "\
+shift/reduce conflict in state 5 on ELSE, after `BOF IF stmt`:
+    stmt -> IF stmt .
+    stmt -> IF stmt . ELSE stmt
+",
+            conflicts[0].to_string()
+        );
+
+        let g = grammar(&["S BOF a EOF", "a b", "a c", "b ID", "c ID"]);
+        let conflicts = ParseTable::build(g).unwrap_err();
+        assert_eq!(1, conflicts.len());
+        assert_eq!(
+            "\
+reduce/reduce conflict in state 2 on EOF, after `BOF ID`:
+    b -> ID .
+    c -> ID .
+",
+            conflicts[0].to_string()
+        );
+    }
+}
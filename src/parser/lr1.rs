@@ -0,0 +1,328 @@
+//! The course's `.lr1` format for parse tables, as written by the reference Jlalr tool.
+//!
+//! A `.lr1` file starts with the grammar, in the `.cfg` format (see `grammar`). Then it lists the
+//! number of states, the number of actions, and the actions themselves, one per line:
+//! ```text
+//! 0 BOF shift 1
+//! 1 expr shift 2
+//! 3 EOF reduce 2
+//! ```
+//! `shift` on a nonterminal is a goto. The initial state is 0, and productions are numbered by
+//! their position in the grammar.
+//!
+//! Tables from Jlalr and from `ParseTable::build` will usually number their states differently.
+//! Use `ParseTable::equivalent` to compare them.
+
+use crate::parser::grammar::{Grammar, ProductionId, Symbol, Terminal};
+use crate::parser::lalr::{Action, ParseTable, StateId};
+use std::collections::HashMap as Map;
+use std::fmt::Write;
+
+impl ParseTable {
+    /// Read a table in the `.lr1` format.
+    pub fn from_lr1(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().enumerate();
+        let grammar = Grammar::read(&mut lines).map_err(|e| e.to_string())?;
+
+        let mut next_line = || match lines.next() {
+            Some((i, line)) => Ok((i + 1, line)),
+            None => Err(String::from("end of file: expected more actions")),
+        };
+        let mut count = || -> Result<usize, String> {
+            let (i, line) = next_line()?;
+            (line.trim().parse()).map_err(|_| format!("line {}: expected a count", i))
+        };
+        let num_states = count()?;
+        if num_states == 0 {
+            return Err(String::from("there has to be at least one state"));
+        }
+        let num_actions = count()?;
+
+        let mut actions = vec![Map::new(); num_states];
+        let mut gotos = vec![Map::new(); num_states];
+        for _ in 0..num_actions {
+            let (i, line) = next_line()?;
+            let err = |msg: &str| format!("line {}: {}", i, msg);
+
+            let words: Vec<_> = line.split_whitespace().collect();
+            let (state, symbol, action, n) = match words[..] {
+                [state, symbol, action, n] => (state, symbol, action, n),
+                _ => return Err(err("expected `<state> <symbol> shift|reduce <number>`")),
+            };
+            let number = |s: &str, limit: usize, what: &str| match s.parse::<usize>() {
+                Ok(n) if n < limit => Ok(n as u32),
+                _ => Err(err(&format!("bad {} `{}`", what, s))),
+            };
+            let state = number(state, num_states, "state")?;
+            let symbol = match grammar.nonterminal(symbol) {
+                Some(nt) => Symbol::Nonterminal(nt),
+                None => match Terminal::from_name(symbol) {
+                    Some(t) if grammar.terminal_index(t).is_some() => Symbol::Terminal(t),
+                    _ => return Err(err(&format!("unknown symbol `{}`", symbol))),
+                },
+            };
+
+            let duplicate = match (symbol, action) {
+                (Symbol::Terminal(t), "shift") => {
+                    let next = StateId(number(n, num_states, "state")?);
+                    actions[state as usize].insert(t, Action::Shift(next))
+                }
+                (Symbol::Terminal(t), "reduce") => {
+                    let p = ProductionId(number(n, grammar.productions().len(), "production")?);
+                    actions[state as usize].insert(t, Action::Reduce(p))
+                }
+                (Symbol::Nonterminal(nt), "shift") => {
+                    let next = StateId(number(n, num_states, "state")?);
+                    gotos[state as usize].insert(nt, next).map(Action::Shift)
+                }
+                (Symbol::Nonterminal(_), "reduce") => {
+                    return Err(err("can't reduce on a nonterminal"));
+                }
+                _ => return Err(err(&format!("unknown action `{}`", action))),
+            };
+            if duplicate.is_some() {
+                return Err(err("more than one action for this state and symbol"));
+            }
+        }
+
+        if let Some((i, _)) = lines.find(|(_, line)| !line.trim().is_empty()) {
+            return Err(format!("line {}: expected end of file", i + 1));
+        }
+        Ok(Self {
+            grammar,
+            actions,
+            gotos,
+        })
+    }
+
+    /// Write the table in the `.lr1` format.
+    pub fn to_lr1(&self) -> String {
+        let grammar = self.grammar();
+        let mut out = grammar.to_cfg();
+
+        let mut lines = vec![];
+        for state in 0..self.num_states() {
+            // Write terminals in the order of the grammar, to keep the output stable.
+            let mut row: Vec<_> = self.actions[state].iter().collect();
+            row.sort_by_key(|(&t, _)| grammar.terminal_index(t));
+            for (t, action) in row {
+                lines.push(match action {
+                    Action::Shift(next) => format!("{} {} shift {}", state, t, next.index()),
+                    Action::Reduce(p) => format!("{} {} reduce {}", state, t, p.index()),
+                });
+            }
+
+            let mut gotos: Vec<_> = self.gotos[state].iter().collect();
+            gotos.sort();
+            for (&nt, next) in gotos {
+                let name = grammar.name(Symbol::Nonterminal(nt));
+                lines.push(format!("{} {} shift {}", state, name, next.index()));
+            }
+        }
+
+        writeln!(out, "{}", self.num_states()).unwrap();
+        writeln!(out, "{}", lines.len()).unwrap();
+        for line in lines {
+            writeln!(out, "{}", line).unwrap();
+        }
+        out
+    }
+
+    /// Do the tables parse the same way? That is, do they have the same grammar, and the same
+    /// actions and gotos, once their states are matched up?
+    ///
+    /// Only states reachable from the initial state are compared.
+    pub fn equivalent(&self, other: &ParseTable) -> bool {
+        if self.grammar() != other.grammar() {
+            return false;
+        }
+
+        // Match up the states, starting from the initial ones.
+        let mut ours_to_theirs = Map::new();
+        let mut theirs_to_ours = Map::new();
+        let mut stack = vec![(StateId::INIT, StateId::INIT)];
+        while let Some((ours, theirs)) = stack.pop() {
+            match (ours_to_theirs.get(&ours), theirs_to_ours.get(&theirs)) {
+                (None, None) => {
+                    ours_to_theirs.insert(ours, theirs);
+                    theirs_to_ours.insert(theirs, ours);
+                }
+                (Some(&t), Some(&o)) if (o, t) == (ours, theirs) => continue,
+                _ => return false,
+            }
+
+            let (our_actions, their_actions) =
+                (&self.actions[ours.index()], &other.actions[theirs.index()]);
+            if our_actions.len() != their_actions.len() {
+                return false;
+            }
+            for (t, &action) in our_actions {
+                match (action, their_actions.get(t)) {
+                    (Action::Shift(a), Some(&Action::Shift(b))) => stack.push((a, b)),
+                    (Action::Reduce(a), Some(&Action::Reduce(b))) if a == b => {}
+                    _ => return false,
+                }
+            }
+
+            let (our_gotos, their_gotos) =
+                (&self.gotos[ours.index()], &other.gotos[theirs.index()]);
+            if our_gotos.len() != their_gotos.len() {
+                return false;
+            }
+            for (nt, &a) in our_gotos {
+                match their_gotos.get(nt) {
+                    Some(&b) => stack.push((a, b)),
+                    None => return false,
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CFG: &str = "\
+6
+BOF
+EOF
+ID
+PLUS
+LPAREN
+RPAREN
+3
+S
+expr
+term
+S
+5
+S BOF expr EOF
+expr expr PLUS term
+expr term
+term ID
+term LPAREN expr RPAREN
+";
+
+    fn table() -> ParseTable {
+        ParseTable::build(Grammar::parse(CFG).unwrap()).unwrap()
+    }
+
+    /// Renumber all the states but the initial one, by reversing their order.
+    fn renumber(lr1: &str, num_states: usize) -> String {
+        let new_id = |s: &str| match s.parse::<usize>().unwrap() {
+            0 => 0,
+            n => num_states - n,
+        };
+        let mut lines: Vec<_> = lr1.lines().map(String::from).collect();
+        for line in &mut lines {
+            let words: Vec<_> = line.split(' ').collect();
+            if let [state, symbol, action, n] = words[..] {
+                if state.parse::<usize>().is_err() {
+                    // A production.
+                    continue;
+                }
+                let n = if action == "shift" {
+                    new_id(n).to_string()
+                } else {
+                    String::from(n)
+                };
+                *line = format!("{} {} {} {}", new_id(state), symbol, action, n);
+            }
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn round_trip() {
+        let table = table();
+        let lr1 = table.to_lr1();
+        assert!(lr1.starts_with(CFG));
+        assert!(lr1.contains("\n0 BOF shift 1\n"));
+
+        let loaded = ParseTable::from_lr1(&lr1).unwrap();
+        assert_eq!(table, loaded);
+        assert_eq!(lr1, loaded.to_lr1());
+    }
+
+    #[test]
+    fn equivalence() {
+        let table = table();
+        let lr1 = table.to_lr1();
+
+        let renumbered = ParseTable::from_lr1(&renumber(&lr1, table.num_states())).unwrap();
+        assert_ne!(table, renumbered);
+        assert!(table.equivalent(&renumbered));
+        assert!(renumbered.equivalent(&table));
+
+        let mut dropped = table.clone();
+        dropped.actions[0].remove(&Terminal::Bof);
+        assert!(!table.equivalent(&dropped));
+
+        // Reduce by the wrong production.
+        let wrong = ParseTable::from_lr1(&lr1.replacen("reduce 3", "reduce 2", 1)).unwrap();
+        assert!(!table.equivalent(&wrong));
+    }
+
+    #[test]
+    fn errors() {
+        let lr1 = table().to_lr1();
+        // Everything but the last line.
+        let head = &lr1[..lr1.len() - lr1.lines().last().unwrap().len() - 1];
+        let last_line = lr1.lines().count();
+        let with_last = |line: &str| format!("{}{}\n", head, line);
+
+        for (text, expected) in [
+            (
+                lr1.replacen("S BOF expr EOF", "S BOF expr END", 1),
+                String::from("line 14: unknown symbol `END`"),
+            ),
+            (
+                with_last("0 BOF jump 1"),
+                format!("line {}: unknown action `jump`", last_line),
+            ),
+            (
+                with_last("0 BOF shift 100"),
+                format!("line {}: bad state `100`", last_line),
+            ),
+            (
+                with_last("0 EOF reduce 5"),
+                format!("line {}: bad production `5`", last_line),
+            ),
+            (
+                with_last("0 expr reduce 1"),
+                format!("line {}: can't reduce on a nonterminal", last_line),
+            ),
+            (
+                with_last("0 WHILE shift 1"),
+                format!("line {}: unknown symbol `WHILE`", last_line),
+            ),
+            (
+                with_last("0 BOF shift"),
+                format!(
+                    "line {}: expected `<state> <symbol> shift|reduce <number>`",
+                    last_line
+                ),
+            ),
+            (
+                with_last("0 BOF shift 2"),
+                format!(
+                    "line {}: more than one action for this state and symbol",
+                    last_line
+                ),
+            ),
+            (
+                format!("{}extra\n", lr1),
+                format!("line {}: expected end of file", last_line + 1),
+            ),
+            (
+                String::from(head),
+                String::from("end of file: expected more actions"),
+            ),
+        ] {
+            let actual = ParseTable::from_lr1(&text).unwrap_err();
+            assert_eq!(expected, actual, "{}", text);
+        }
+    }
+}
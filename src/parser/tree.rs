@@ -0,0 +1,67 @@
+//! Concrete parse trees, as built by `Parser::parse`.
+
+use crate::parser::grammar::{Grammar, ProductionId, Symbol, Terminal};
+use crate::source_map::Span;
+use crate::tokenizer::tokens::TokenInfo;
+
+/// A parse tree, with a leaf for every token of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseTree<'a> {
+    /// A token of the input, as it came from the tokenizer.
+    Leaf(TokenInfo<'a>),
+    /// `BOF` or `EOF`, which the parser adds around the input.
+    Marker(Terminal),
+    /// A use of a production. There's one child per symbol of its right-hand side.
+    Node {
+        production: ProductionId,
+        children: Vec<ParseTree<'a>>,
+    },
+}
+
+impl<'a> ParseTree<'a> {
+    /// The symbol at the root of the tree.
+    pub fn symbol(&self, grammar: &Grammar) -> Symbol {
+        match self {
+            ParseTree::Leaf(token) => Symbol::Terminal(Terminal::Token(token.val.kind())),
+            ParseTree::Marker(t) => Symbol::Terminal(*t),
+            ParseTree::Node { production, .. } => {
+                Symbol::Nonterminal(grammar.production(*production).lhs)
+            }
+        }
+    }
+
+    /// The tokens at the leaves of the tree, in order.
+    pub fn tokens(&self) -> Vec<&TokenInfo<'a>> {
+        let mut tokens = vec![];
+        let mut stack = vec![self];
+        while let Some(tree) = stack.pop() {
+            match tree {
+                ParseTree::Leaf(token) => tokens.push(token),
+                ParseTree::Marker(_) => {}
+                ParseTree::Node { children, .. } => stack.extend(children.iter().rev()),
+            }
+        }
+        tokens
+    }
+
+    /// From the first token to the last. `None` if the tree has no tokens.
+    pub fn span(&self) -> Option<Span> {
+        let tokens = self.tokens();
+        let (first, last) = (tokens.first()?, tokens.last()?);
+        Some(first.span().to(last.span()))
+    }
+
+    /// The tree as an S-expression, e.g. `(expr (expr (term x)) + (term y))`. Nodes are labelled
+    /// with their nonterminal, and leaves are written as their lexeme.
+    pub fn sexp(&self, grammar: &Grammar) -> String {
+        match self {
+            ParseTree::Leaf(token) => String::from(token.lexeme),
+            ParseTree::Marker(t) => t.to_string(),
+            ParseTree::Node { children, .. } => {
+                let mut words = vec![String::from(grammar.name(self.symbol(grammar)))];
+                words.extend(children.iter().map(|c| c.sexp(grammar)));
+                format!("({})", words.join(" "))
+            }
+        }
+    }
+}
@@ -0,0 +1,352 @@
+//! This module keeps track of all the source files in a compilation, so that any part of the
+//! compiler can point back at the input.
+//!
+//! Files are loaded into a `SourceMap`, which hands out a `FileId` for each one. A `Span` is a
+//! compact reference to a range of bytes in one of those files; the `SourceMap` can resolve it back
+//! to a line and column, or to the source text itself.
+
+use crate::diagnostics::{ColorMode, Diagnostic};
+use crate::tokenizer::{unicode, Position};
+use std::io::{self, Read};
+use std::iter;
+
+/// Identifies a file in a `SourceMap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(u32);
+
+impl FileId {
+    /// Used for input that isn't registered in any `SourceMap`; e.g., lines passed directly to
+    /// `Tokenizer::tokenize`. Spans in this "file" can't be resolved.
+    pub const ANONYMOUS: FileId = FileId(u32::MAX);
+}
+
+/// A range of bytes in a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file: FileId,
+    /// Byte offset from the start of the file. Inclusive.
+    pub lo: u32,
+    /// Byte offset from the start of the file. Exclusive.
+    pub hi: u32,
+}
+
+impl Span {
+    pub fn new(file: FileId, lo: usize, hi: usize) -> Self {
+        assert!(lo <= hi);
+        Self {
+            file,
+            lo: lo as u32,
+            hi: hi as u32,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        (self.hi - self.lo) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lo == self.hi
+    }
+
+    /// The smallest span covering both `self` and `other`, which must be in the same file.
+    pub fn to(self, other: Span) -> Span {
+        assert_eq!(self.file, other.file);
+        Span {
+            file: self.file,
+            lo: self.lo.min(other.lo),
+            hi: self.hi.max(other.hi),
+        }
+    }
+}
+
+/// A source file that has been loaded into memory.
+#[derive(Debug)]
+pub struct SourceFile {
+    id: FileId,
+    name: String,
+    src: String,
+    /// The byte offset of the start of each line. Always non-empty; the first entry is zero.
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub(crate) fn new(id: FileId, name: String, src: String) -> Self {
+        let line_starts = lines(&src).map(|(start, _)| start).collect();
+        Self {
+            id,
+            name,
+            src,
+            line_starts,
+        }
+    }
+
+    pub fn id(&self) -> FileId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    pub fn num_lines(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The byte offset of the start of a line. Zero-indexed.
+    pub fn line_start(&self, line_num: usize) -> usize {
+        self.line_starts[line_num]
+    }
+
+    /// The text of a line, without its line terminator. Zero-indexed.
+    pub fn line(&self, line_num: usize) -> &str {
+        let start = self.line_starts[line_num];
+        let end = match self.line_starts.get(line_num + 1) {
+            Some(&next) => next,
+            None => self.src.len(),
+        };
+
+        // Any CR is part of a line terminator, so there's no risk of stripping too much.
+        let line = &self.src[start..end];
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        line.strip_suffix('\r').unwrap_or(line)
+    }
+
+    /// All the lines of the file, each paired with the byte offset of its start.
+    pub fn lines(&self) -> impl Iterator<Item = (usize, &str)> + Clone + '_ {
+        (0..self.num_lines()).map(move |i| (self.line_start(i), self.line(i)))
+    }
+
+    /// The position of a byte offset in this file.
+    ///
+    /// `offset` may point at a line terminator, or at the very end of the file.
+    pub fn position(&self, offset: usize) -> Position<'_> {
+        assert!(offset <= self.src.len());
+
+        // The last line that starts at or before `offset`.
+        let line_num = match self.line_starts.binary_search(&offset) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        let line_start = self.line_starts[line_num];
+        let line = self.line(line_num);
+
+        Position {
+            file: self.id,
+            line,
+            line_num,
+            line_start,
+            // Clamp positions inside the line terminator to the "newline" position.
+            col: (offset - line_start).min(line.len()),
+        }
+    }
+}
+
+/// All the source files in a compilation.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a file into the source map.
+    ///
+    /// `name` is used when reporting errors; usually it's the file's path.
+    pub fn add_file(&mut self, name: impl Into<String>, src: impl Into<String>) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        assert_ne!(id, FileId::ANONYMOUS, "Too many files");
+
+        self.files
+            .push(SourceFile::new(id, name.into(), src.into()));
+        id
+    }
+
+    /// Read a whole file into the source map.
+    ///
+    /// Invalid UTF-8 is replaced with U+FFFD; see `unicode::decode_utf8`.
+    pub fn read_file(
+        &mut self,
+        name: impl Into<String>,
+        mut input: impl Read,
+    ) -> io::Result<FileId> {
+        let mut bytes = vec![];
+        input.read_to_end(&mut bytes)?;
+        let src = unicode::decode_utf8(&bytes).into_owned();
+        Ok(self.add_file(name, src))
+    }
+
+    /// Panics if the file isn't in this source map.
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        self.files
+            .get(id.0 as usize)
+            .expect("File not in this SourceMap")
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &SourceFile> {
+        self.files.iter()
+    }
+
+    /// The source text covered by a span.
+    pub fn snippet(&self, span: Span) -> &str {
+        &self.file(span.file).src()[span.lo as usize..span.hi as usize]
+    }
+
+    /// The position of the start of a span.
+    pub fn start(&self, span: Span) -> Position<'_> {
+        self.file(span.file).position(span.lo as usize)
+    }
+
+    /// The position of the last character in a span. For an empty span, this is the same as
+    /// `start`.
+    pub fn end_inclusive(&self, span: Span) -> Position<'_> {
+        let file = self.file(span.file);
+        if span.is_empty() {
+            return file.position(span.lo as usize);
+        }
+
+        // Step back to the start of the last char.
+        let mut last = span.hi as usize - 1;
+        while !file.src().is_char_boundary(last) {
+            last -= 1;
+        }
+        file.position(last)
+    }
+
+    /// Format a span's location for the user, as `file:line:col`. Lines and columns are
+    /// one-indexed.
+    pub fn location(&self, span: Span) -> String {
+        let pos = self.start(span);
+        format!(
+            "{}:{}:{}",
+            self.file(span.file).name(),
+            pos.line_num + 1,
+            pos.line[..pos.col].chars().count() + 1
+        )
+    }
+
+    /// Render a diagnostic, naming the file it points into.
+    pub fn render(&self, diagnostic: &Diagnostic, mode: ColorMode) -> String {
+        let file = diagnostic.start.file;
+        let name = if file == FileId::ANONYMOUS {
+            "<unknown>"
+        } else {
+            self.file(file).name()
+        };
+        diagnostic.render(name, mode)
+    }
+}
+
+/// Split `src` into lines, each paired with the byte offset of its start. The lines don't include
+/// their line terminators.
+///
+/// As in JLS 3.4, a line terminator is any of CR, LF, or CR LF. A terminator at the very end of
+/// `src` ends the last line, rather than starting a new empty one. Empty input has one empty line.
+pub fn lines(src: &str) -> impl Iterator<Item = (usize, &str)> + Clone + '_ {
+    let mut next_start = Some(0);
+    iter::from_fn(move || {
+        let start = next_start?;
+        let rest = &src[start..];
+        match rest.find(['\n', '\r']) {
+            Some(len) => {
+                let terminator = if rest[len..].starts_with("\r\n") {
+                    2
+                } else {
+                    1
+                };
+                let end = start + len + terminator;
+                next_start = if end == src.len() { None } else { Some(end) };
+                Some((start, &rest[..len]))
+            }
+            None => {
+                next_start = None;
+                Some((start, rest))
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_lines() {
+        let mut map = SourceMap::new();
+        let a = map.add_file("A.java", "class A {\r\n}\n");
+        let b = map.add_file("B.java", "\nclass B {}");
+        let empty = map.add_file("Empty.java", "");
+
+        let lines: Vec<_> = map.file(a).lines().collect();
+        assert_eq!(vec![(0, "class A {"), (11, "}")], lines);
+
+        let lines: Vec<_> = map.file(b).lines().collect();
+        assert_eq!(vec![(0, ""), (1, "class B {}")], lines);
+
+        let lines: Vec<_> = map.file(empty).lines().collect();
+        assert_eq!(vec![(0, "")], lines);
+    }
+
+    #[test]
+    fn line_terminators() {
+        for (input, expected) in [
+            ("a\nb", vec![(0, "a"), (2, "b")]),
+            ("a\rb", vec![(0, "a"), (2, "b")]),
+            ("a\r\nb", vec![(0, "a"), (3, "b")]),
+            ("a\n\rb\r", vec![(0, "a"), (2, ""), (3, "b")]),
+            ("a\r\rb\r\n", vec![(0, "a"), (2, ""), (3, "b")]),
+            ("\r\n\n", vec![(0, ""), (2, "")]),
+            ("", vec![(0, "")]),
+        ] {
+            let actual: Vec<_> = lines(input).collect();
+            assert_eq!(expected, actual, "{:?}", input);
+
+            let mut map = SourceMap::new();
+            let id = map.add_file("A.java", input);
+            let actual: Vec<_> = map.file(id).lines().collect();
+            assert_eq!(expected, actual, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn read_file() {
+        let mut map = SourceMap::new();
+        let id = map
+            .read_file("A.java", &b"int x;\r\nchar c = '\xff';"[..])
+            .unwrap();
+        let file = map.file(id);
+        assert_eq!(2, file.num_lines());
+        assert_eq!("char c = '\u{FFFD}';", file.line(1));
+    }
+
+    #[test]
+    fn resolve_spans() {
+        let mut map = SourceMap::new();
+        map.add_file("A.java", "class A {}\n");
+        let b = map.add_file("B.java", "class B {\n  int x;\n}\n");
+
+        let span = Span::new(b, 12, 15);
+        assert_eq!("int", map.snippet(span));
+        assert_eq!("B.java:2:3", map.location(span));
+
+        let start = map.start(span);
+        assert_eq!((start.line, start.line_num, start.col), ("  int x;", 1, 2));
+        let end = map.end_inclusive(span);
+        assert_eq!((end.line_num, end.col), (1, 4));
+
+        // A span covering multiple lines.
+        let span = Span::new(b, 8, 20);
+        assert_eq!("{\n  int x;\n}", map.snippet(span));
+        assert_eq!(map.end_inclusive(span).line_num, 2);
+
+        // The end of the file.
+        let pos = map.file(b).position(21);
+        assert_eq!((pos.line_num, pos.col), (2, 1));
+    }
+}
@@ -0,0 +1,28 @@
+//! Helpers shared by the unit tests.
+
+/// A small xorshift generator, so randomized tests are reproducible without pulling in `rand`.
+pub struct Rng(u32);
+
+impl Rng {
+    /// The seed must be nonzero.
+    pub fn new(seed: u32) -> Rng {
+        assert_ne!(seed, 0);
+        Rng(seed)
+    }
+
+    /// A number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as usize % n
+    }
+}
+
+/// A Joos 1W file with `body` as the body of a method.
+pub fn method(body: &str) -> String {
+    format!(
+        "public class A {{ public A() {{}} public void m() {{ {} }} }}",
+        body
+    )
+}
@@ -15,71 +15,492 @@
 //! let tokenizer = Tokenizer::new();
 //!
 //! for token in tokenizer.tokenize(input.into_iter()) {
-//!     // do something interesting ...
-//!     dbg!(token);
+//!     match token {
+//!         // do something interesting ...
+//!         Ok(token) => println!("{:?}", token),
+//!         // Scanning resumes after an error, so there may be more than one.
+//!         Err(e) => println!("{}", e),
+//!     }
 //! }
 //! ```
 
-use dfa::DFA;
-use states::{State, StateSet, Symbol};
-use tokens::{TokenInfo, TokenOrComment};
+use crate::source_map::{self, FileId, SourceFile, Span};
+use incremental::IncrementalTokens;
+use int_literals::IntLiteralChecker;
+use lexer::{Lexeme, Lexer, LexerSpec};
+use lossless::LosslessFile;
+use states::{AcceptedStateLabel, Symbol};
+use std::fmt;
+use std::iter;
+use std::sync::OnceLock;
+use tokens::{Token, TokenError, TokenInfo, TokenOrComment};
 
 mod dfa;
-mod joos_1w_nfa;
+pub mod dump;
+pub mod incremental;
+pub mod int_literals;
+pub mod lexer;
+pub mod lossless;
+mod minimize_dfa;
 mod nfa;
 mod nfa_to_dfa;
+mod regex;
 mod states;
 pub mod token_types;
 pub mod tokens;
+pub mod unicode;
 
-/// Tokenizer for the Joos 1W language.
+/// Tokenizer for the Joos 1W language, or another `Dialect`.
+///
+/// This is a `Lexer` for the spec in `joos_1w.lex` (plus `java_1_3.lex` for Java), plus the logic
+/// to turn its lexemes into Joos tokens.
 #[derive(Debug)]
 pub struct Tokenizer {
-    dfa: DFA<StateSet<State>>,
+    dialect: Dialect,
+    lexer: Lexer,
+    /// The Joos token type of each rule, indexed by `RuleId::index`.
+    labels: Vec<AcceptedStateLabel>,
 }
 
-impl Tokenizer {
-    /// Compile an NFA for the lexical grammar of Joos 1W into a DFA.
+/// Size of a lexer's DFA. The implicit "dead" state isn't counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DfaStats {
+    /// States of the DFA straight out of the powerset construction, before minimization.
+    ///
+    /// Only known if the DFA was built from scratch with `Lexer::new`.
+    pub raw_states: Option<usize>,
+    pub minimized_states: usize,
+    /// The number of (state, symbol) pairs with a transition.
+    pub minimized_transitions: usize,
+    /// Columns in the compiled transition table. Symbols that every state treats the same share a
+    /// column.
+    pub symbol_classes: usize,
+}
+
+/// Which language to tokenize.
+///
+/// The Joos dialects are the same, lexically. Java 1.3 adds the literals that Joos leaves out: hex,
+/// octal, long, float, and double.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Dialect {
+    #[default]
+    Joos1W,
+    Joos2,
+    Java1_3,
+}
+
+/// The lexical grammar of Joos 1W, in the format of `lexer::LexerSpec`.
+const JOOS_1W_SPEC: &str = include_str!("tokenizer/joos_1w.lex");
+
+/// The extra rules for Java 1.3, on top of `JOOS_1W_SPEC`.
+const JAVA_1_3_SPEC: &str = include_str!("tokenizer/java_1_3.lex");
+
+/// The pre-built DFA for `JOOS_1W_SPEC`, in the format of `dfa::table`.
+///
+/// To regenerate this after changing the spec, run `REGENERATE_TABLES=1 cargo test tables`.
+const JOOS_1W_TABLE: &str = include_str!("tokenizer/joos_1w.dfa");
+
+/// The pre-built DFA for Java 1.3.
+const JAVA_1_3_TABLE: &str = include_str!("tokenizer/java_1_3.dfa");
+
+impl Dialect {
+    pub const ALL: [Dialect; 3] = [Dialect::Joos1W, Dialect::Joos2, Dialect::Java1_3];
+
+    /// Is this token part of the dialect?
     ///
-    /// Be warned that this is an expensive operation. Best to avoid calling this in a loop (e.g.
-    /// in test cases, etc.)
+    /// Joos leaves out a lot of Java's keywords and operators, but they're still tokenized, so that
+    /// `Tokenizer::check_supported` can point them out. That beats a generic parse error.
+    pub fn supports(self, token: &Token) -> bool {
+        use token_types::Keyword::*;
+        use token_types::Literal::{Bool, Char, Int, Null, StringLit};
+        use token_types::Operator::*;
+
+        if self == Dialect::Java1_3 {
+            return true;
+        }
+        match token {
+            Token::Keyword(k) => !matches!(
+                k,
+                Break
+                    | Case
+                    | Catch
+                    | Const
+                    | Continue
+                    | Default
+                    | Do
+                    | Double
+                    | Finally
+                    | Float
+                    | Goto
+                    | Long
+                    | Private
+                    | Strictfp
+                    | Switch
+                    | Synchronized
+                    | Transient
+                    | Try
+                    | Volatile
+            ),
+            Token::Operator(o) => !matches!(
+                o,
+                BitNot
+                    | Question
+                    | Colon
+                    | Increment
+                    | Decrement
+                    | BirXor
+                    | LShift
+                    | RShift
+                    | URShift
+                    | PlusEq
+                    | MinusEq
+                    | TimesEq
+                    | DivideEq
+                    | BitAndEq
+                    | BitOrEq
+                    | BitXorEq
+                    | ModEq
+                    | LShiftEq
+                    | RShiftEq
+                    | URShiftEq
+            ),
+            Token::Literal(l) => matches!(l, Int(_) | Bool(_) | Char(_) | StringLit(_) | Null),
+            Token::Identifier(_) | Token::Separator(_) => true,
+        }
+    }
+
+    /// The dialect with the same lexical grammar, whose table and name we use. Joos 2 is lexically
+    /// the same as Joos 1W.
+    fn lexical(self) -> Self {
+        match self {
+            Dialect::Joos1W | Dialect::Joos2 => Dialect::Joos1W,
+            Dialect::Java1_3 => Dialect::Java1_3,
+        }
+    }
+
+    fn spec(self) -> LexerSpec {
+        let parse =
+            |name, text| LexerSpec::parse(text).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        let mut spec = parse("joos_1w.lex", JOOS_1W_SPEC);
+        if self.lexical() == Dialect::Java1_3 {
+            for rule in parse("java_1_3.lex", JAVA_1_3_SPEC).rules() {
+                spec.add_rule(&rule.name, rule.action, &rule.pattern)
+                    .unwrap_or_else(|e| panic!("java_1_3.lex: {}", e));
+            }
+        }
+        spec
+    }
+
+    fn table(self) -> &'static str {
+        match self.lexical() {
+            Dialect::Java1_3 => JAVA_1_3_TABLE,
+            _ => JOOS_1W_TABLE,
+        }
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Dialect::Joos1W => "Joos 1W",
+            Dialect::Joos2 => "Joos 2",
+            Dialect::Java1_3 => "Java 1.3",
+        })
+    }
+}
+
+impl Tokenizer {
+    /// Load the pre-built DFA for Joos 1W. This is cheap.
     pub fn new() -> Self {
-        let nfa = joos_1w_nfa::nfa();
-        let dfa = nfa.to_dfa();
+        Self::with_dialect(Dialect::Joos1W)
+    }
+
+    /// Load the pre-built DFA for a dialect. This is cheap.
+    pub fn with_dialect(dialect: Dialect) -> Self {
+        let lexer =
+            Lexer::from_table(dialect.spec(), dialect.table()).expect("Corrupt tokenizer table");
+        Self::from_lexer(dialect, lexer)
+    }
+
+    /// A shared tokenizer, loaded the first time it's needed.
+    pub fn global() -> &'static Tokenizer {
+        static TOKENIZER: OnceLock<Tokenizer> = OnceLock::new();
+        TOKENIZER.get_or_init(Tokenizer::new)
+    }
+
+    /// Compile the lexical grammar of a dialect into a minimal DFA, from scratch.
+    ///
+    /// Be warned that this is an expensive operation. `with_dialect` loads the same DFA from a
+    /// table, so this is only needed to check or regenerate that table.
+    pub fn build(dialect: Dialect) -> Self {
+        Self::from_lexer(dialect, Lexer::new(dialect.spec()))
+    }
+
+    fn from_lexer(dialect: Dialect, lexer: Lexer) -> Self {
+        let labels = lexer
+            .spec()
+            .rules()
+            .iter()
+            .map(|rule| {
+                AcceptedStateLabel::from_rule_name(&rule.name)
+                    .unwrap_or_else(|| panic!("No Joos token type for rule `{}`", rule.name))
+            })
+            .collect();
+        Self {
+            dialect,
+            lexer,
+            labels,
+        }
+    }
+
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Report each token that Java has, but this tokenizer's dialect doesn't; e.g. `>>=` or
+    /// `switch` in Joos. See `Dialect::supports`.
+    pub fn check_supported<'a>(&self, tokens: &[TokenInfo<'a>]) -> Vec<TokenError<'a>> {
+        tokens
+            .iter()
+            .filter(|t| !self.dialect.supports(&t.val))
+            .map(|t| TokenError::NotInDialect {
+                start: t.start,
+                lexeme: t.lexeme,
+                dialect: self.dialect,
+            })
+            .collect()
+    }
 
-        Self { dfa }
+    pub fn lexer(&self) -> &Lexer {
+        &self.lexer
+    }
+
+    pub fn dfa_stats(&self) -> DfaStats {
+        self.lexer.dfa_stats()
+    }
+
+    /// Dump the DFA as a table, in the same format as the pre-built one.
+    pub fn dfa_table(&self) -> String {
+        let mut table = format!(
+            "# The DFA for the {} tokenizer. Generated by `Tokenizer::build`; don't edit.\n\
+             # To regenerate, run `REGENERATE_TABLES=1 cargo test tables`.\n",
+            self.dialect.lexical()
+        );
+        table.push_str(&self.lexer.to_table());
+        table
     }
 
     /// Tokenize the input, stripping out comments.
+    ///
+    /// Errors are reported in-line, and scanning resumes after each one.
     pub fn tokenize<'a>(
         &'a self,
-        lines: impl Iterator<Item = &'a str> + Clone + 'a,
-    ) -> impl Iterator<Item = TokenInfo> + 'a {
-        self.tokenize_keep_comments(lines)
+        input: impl Input<'a>,
+    ) -> impl Iterator<Item = Result<TokenInfo<'a>, TokenError<'a>>> + 'a {
+        self.tokenize_keep_comments(input)
             .filter_map(|elem| match elem {
-                TokenOrComment::Token(t) => Some(t),
-                TokenOrComment::LineComment { .. } => None,
-                TokenOrComment::StarComment { .. } => None,
+                Ok(TokenOrComment::Token(t)) => Some(Ok(t)),
+                Ok(_) => None,
+                Err(e) => Some(Err(e)),
             })
     }
 
     /// Run the "max munch" scanning algorithm to tokenize the input.
+    ///
+    /// Each Javadoc comment is also attached to the token after it; see `TokenInfo::doc`. Int
+    /// literals are range-checked based on the tokens before them; see `int_literals`.
+    ///
+    /// Errors are reported in-line, and scanning resumes after each one.
     pub fn tokenize_keep_comments<'a>(
         &'a self,
-        lines: impl Iterator<Item = &'a str> + Clone + 'a,
-    ) -> impl Iterator<Item = TokenOrComment> + 'a {
-        self.dfa.tokenize(all_positions(lines))
+        input: impl Input<'a>,
+    ) -> impl Iterator<Item = Result<TokenOrComment<'a>, TokenError<'a>>> + 'a {
+        self.elems(self.lexer.lex(input))
+    }
+
+    /// Turn lexemes into tokens and comments, as in `tokenize_keep_comments`.
+    fn elems<'a>(
+        &'a self,
+        lexemes: impl Iterator<Item = Result<Lexeme<'a>, TokenError<'a>>> + 'a,
+    ) -> impl Iterator<Item = Result<TokenOrComment<'a>, TokenError<'a>>> + 'a {
+        let mut doc = None;
+        let mut ints = IntLiteralChecker::new();
+        lexemes
+            .filter_map(move |result| match result {
+                Ok(l) => dfa::token_or_comment(&self.labels[l.rule.index()], l),
+                Err(e) => Some(Err(e)),
+            })
+            .map(move |mut elem| {
+                match &mut elem {
+                    Ok(TokenOrComment::DocComment(c)) => doc = Some(Box::new(c.clone())),
+                    Ok(TokenOrComment::Token(t)) => {
+                        t.doc = doc.take();
+                        ints.check(t)?;
+                    }
+                    _ => (),
+                }
+                elem
+            })
+    }
+
+    /// Tokenize the entire input, stripping out comments. Collect all the errors separately.
+    ///
+    /// The tokens are what's left after skipping over the bad input, so if there are any errors,
+    /// you probably shouldn't try to parse them.
+    pub fn tokenize_all<'a>(
+        &'a self,
+        input: impl Input<'a>,
+    ) -> (Vec<TokenInfo<'a>>, Vec<TokenError<'a>>) {
+        let mut tokens = vec![];
+        let mut errors = vec![];
+        for result in self.tokenize(input) {
+            match result {
+                Ok(t) => tokens.push(t),
+                Err(e) => errors.push(e),
+            }
+        }
+        (tokens, errors)
+    }
+
+    /// Tokenize a text that's going to be edited, e.g. in an editor. After each edit, only what the
+    /// edit affects is re-tokenized; see `incremental`.
+    pub fn tokenize_incremental(&self, src: impl Into<String>) -> IncrementalTokens<'_> {
+        IncrementalTokens::new(self, src.into())
+    }
+
+    /// Tokenize the input, keeping all whitespace and comments as trivia around the tokens, so
+    /// that the source can be rebuilt exactly. See `lossless`.
+    ///
+    /// Since bad input has no place in the result, this fails if there are any errors.
+    pub fn tokenize_lossless<'a>(
+        &'a self,
+        input: impl WholeInput<'a>,
+    ) -> Result<LosslessFile<'a>, Vec<TokenError<'a>>> {
+        let mut builder = lossless::Builder::new(input.src(), input.file());
+        let mut errors = vec![];
+        for result in self.tokenize_keep_comments(input) {
+            match result {
+                Ok(elem) => builder.push(elem),
+                Err(e) => errors.push(e),
+            }
+        }
+        if errors.is_empty() {
+            Ok(builder.finish())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An input stream for the tokenizer.
+///
+/// This is one of:
+/// - A `SourceFile`. To tokenize an `io::Read`, load it with `SourceMap::read_file` first.
+/// - `Text`, for a whole string that hasn't been split into lines.
+/// - An iterator of lines (without line terminators). Each line is assumed to have been followed
+///   by a single `\n`, so if that isn't the case, the byte offsets in spans will be off.
+///
+/// Spans of tokens from anything but a `SourceFile` aren't associated with any file; they have
+/// `FileId::ANONYMOUS`.
+pub trait Input<'a>: Sized {
+    /// The file the input comes from.
+    fn file(&self) -> FileId;
+
+    /// The lines of the input, without line terminators, each paired with the byte offset of its
+    /// start.
+    fn lines(self) -> impl Iterator<Item = (usize, &'a str)> + Clone + 'a;
+
+    /// Flatten the input into an iterator of positions, including special "newline" positions
+    /// after each line.
+    fn positions(self) -> impl Iterator<Item = Position<'a>> + Clone + 'a {
+        all_positions(self.file(), self.lines())
+    }
+}
+
+impl<'a, I> Input<'a> for I
+where
+    I: Iterator<Item = &'a str> + Clone + 'a,
+{
+    fn file(&self) -> FileId {
+        FileId::ANONYMOUS
+    }
+
+    fn lines(self) -> impl Iterator<Item = (usize, &'a str)> + Clone + 'a {
+        // Keep track of the offset of the start of each line, assuming each line was followed by
+        // a single newline character.
+        self.scan(0, |offset, line| {
+            let line_start = *offset;
+            *offset += line.len() + 1;
+            Some((line_start, line))
+        })
+    }
+}
+
+/// A whole string of input, e.g. the contents of a file.
+///
+/// Lines may be terminated by any of CR, LF, or CR LF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Text<'a>(pub &'a str);
+
+impl<'a> Input<'a> for Text<'a> {
+    fn file(&self) -> FileId {
+        FileId::ANONYMOUS
+    }
+
+    fn lines(self) -> impl Iterator<Item = (usize, &'a str)> + Clone + 'a {
+        source_map::lines(self.0)
+    }
+}
+
+impl<'a> Input<'a> for &'a SourceFile {
+    fn file(&self) -> FileId {
+        self.id()
+    }
+
+    fn lines(self) -> impl Iterator<Item = (usize, &'a str)> + Clone + 'a {
+        SourceFile::lines(self)
+    }
+}
+
+/// Input that's available as one string, line terminators and all.
+pub trait WholeInput<'a>: Input<'a> {
+    fn src(&self) -> &'a str;
+}
+
+impl<'a> WholeInput<'a> for Text<'a> {
+    fn src(&self) -> &'a str {
+        self.0
+    }
+}
+
+impl<'a> WholeInput<'a> for &'a SourceFile {
+    fn src(&self) -> &'a str {
+        SourceFile::src(self)
     }
 }
 
 /// A position in the input stream.
+///
+/// Each position refers to a single source character, which may take up more than one byte of
+/// `line`: either a non-ASCII character, or a unicode escape like `\u0041`. See `unicode`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Position<'a> {
-    /// Does not contain a newline character.
+    pub file: FileId,
+    /// Does not contain a line terminator.
     pub line: &'a str,
     /// Zero-indexed.
     pub line_num: usize,
-    /// Invariant: 0 <= col <= line.len()
+    /// The byte offset of the start of `line` in the file.
+    pub line_start: usize,
+    /// A byte offset into `line`.
+    ///
+    /// Invariant: 0 <= col <= line.len(), and col is the start of a source character.
     pub col: usize,
 }
 
@@ -89,53 +510,93 @@ impl<'a> Position<'a> {
     /// As a special case, if the current position is 1 past the end of the current line, then we
     /// return a "newline" symbol. This makes up for `line` not containing a newline character.
     fn symbol(&self) -> Symbol {
-        assert!(self.col <= self.line.len());
-        if self.col == self.line.len() {
-            return Symbol::new(b'\n');
-        }
+        Symbol::from_char(self.source_char())
+    }
 
-        let b = self.line.as_bytes()[self.col];
+    /// The source character at this position, after unicode escape translation.
+    ///
+    /// This is a newline if the current position is 1 past the end of the current line.
+    ///
+    /// A malformed unicode escape comes out as just its backslash. The scanner reports it.
+    pub fn source_char(&self) -> char {
+        unicode::decode(*self).map_or('\\', |(c, _)| c)
+    }
 
-        // todo gracefully handle encoding errors!!
-        assert!(b < 128, "Not ASCII: 0x{:x}", b);
+    /// The byte offset of this position in the file.
+    pub fn offset(&self) -> usize {
+        self.line_start + self.col
+    }
 
-        Symbol::new(b)
+    /// The span from this position, up to and including `end_inclusive`.
+    pub fn span_to(&self, end_inclusive: Position) -> Span {
+        let hi = end_inclusive.offset() + end_inclusive.width();
+        Span::new(self.file, self.offset(), hi)
+    }
+
+    /// The number of bytes the source character at this position takes up in `line`.
+    ///
+    /// This is zero for the "newline" position at the end of the line.
+    pub fn width(&self) -> usize {
+        unicode::decode(*self).map_or(1, |(_, width)| width)
     }
 }
 
 /// Turn an iterator of lines into a flattened iterator of positions.
 ///
-/// Includes special "newline" positions after each line.
+/// Each line is paired with the byte offset of its start. Includes special "newline" positions
+/// after each line.
 fn all_positions<'a>(
-    lines: impl Iterator<Item = &'a str> + Clone + 'a,
+    file: FileId,
+    lines: impl Iterator<Item = (usize, &'a str)> + Clone + 'a,
 ) -> impl Iterator<Item = Position<'a>> + Clone + 'a {
     lines
         .enumerate()
-        .flat_map(|(line_num, line)| line_positions(line_num, line))
+        .flat_map(move |(line_num, (line_start, line))| {
+            line_positions(file, line_num, line_start, line)
+        })
 }
 
 /// Turn a single line into an iterator of all positions in that line.
 ///
 /// Includes a special "newline" position after the end of the line.
 fn line_positions<'a>(
+    file: FileId,
     line_num: usize,
+    line_start: usize,
     line: &'a str,
 ) -> impl Iterator<Item = Position<'a>> + Clone + 'a {
-    // Note the inclusive range. This is crucial since `line` itself has no newline.
-    (0..=line.len()).map(move |col| Position {
-        line_num,
+    let first = Position {
+        file,
         line,
-        col,
+        line_num,
+        line_start,
+        col: 0,
+    };
+
+    // Note that we stop *after* the position at `line.len()`. This is crucial since `line` itself
+    // has no newline.
+    iter::successors(Some(first), |pos| {
+        if pos.col == pos.line.len() {
+            None
+        } else {
+            Some(Position {
+                col: pos.col + pos.width(),
+                ..*pos
+            })
+        }
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::diagnostics::ColorMode;
+    use crate::source_map::SourceMap;
     use token_types::Keyword::{Else, If, While};
+    use token_types::Literal::StringLit;
     use token_types::Operator::{Assign, Le};
-    use token_types::Separator::{Comma, Dot, LBrace, RBrace};
-    use tokens::Token::{self, Keyword, Operator, Separator};
+    use token_types::Separator::{Comma, Dot, LBrace, RBrace, Semicolon};
+    use tokens::Token::{self, Keyword, Literal, Operator, Separator};
 
     /// A test case for the tokenizer. Only the tokens' inner values are checked.
     pub struct TestCase<'a> {
@@ -148,7 +609,7 @@ mod tests {
         pub fn run(self, tokenizer: &Tokenizer) {
             let mut actual = vec![];
             for token in tokenizer.tokenize(self.input.into_iter()) {
-                actual.push(token.val);
+                actual.push(token.unwrap().val);
             }
             assert_eq!(self.expected_output, actual);
         }
@@ -165,8 +626,8 @@ mod tests {
     impl<'a> DetailedTestCase<'a> {
         /// Panics if the input doesn't tokenize as expected.
         fn run(self, tokenizer: &Tokenizer) {
-            let actual: Vec<_> = tokenizer.tokenize(self.input.into_iter()).collect();
-            assert_eq!(self.expected_output, actual);
+            let actual: Result<Vec<_>, _> = tokenizer.tokenize(self.input.into_iter()).collect();
+            assert_eq!(Ok(self.expected_output), actual);
         }
     }
 
@@ -175,7 +636,7 @@ mod tests {
     fn simple_examples() {
         let tokenizer = Tokenizer::new();
 
-        for (input, expected_output) in vec![
+        for (input, expected_output) in [
             (vec![""], vec![]),
             (
                 vec!["if while else"],
@@ -215,51 +676,66 @@ mod tests {
         let if_ = TokenInfo {
             val: Keyword(If),
             start: Position {
+                file: FileId::ANONYMOUS,
                 line: input[0],
                 line_num: 0,
+                line_start: 0,
                 col: 0,
             },
             lexeme: "if",
+            doc: None,
         };
 
         let while_ = TokenInfo {
             val: Keyword(While),
             start: Position {
+                file: FileId::ANONYMOUS,
                 line: input[0],
                 line_num: 0,
+                line_start: 0,
                 col: 3,
             },
             lexeme: "while",
+            doc: None,
         };
 
         let else_ = TokenInfo {
             val: Keyword(Else),
             start: Position {
+                file: FileId::ANONYMOUS,
                 line: input[0],
                 line_num: 0,
+                line_start: 0,
                 col: 9,
             },
             lexeme: "else",
+            doc: None,
         };
 
         let left = TokenInfo {
             val: Separator(LBrace),
             start: Position {
+                file: FileId::ANONYMOUS,
                 line: input[2],
                 line_num: 2,
+                line_start: 15,
                 col: 0,
             },
             lexeme: "{",
+            doc: None,
         };
 
         let right = TokenInfo {
             val: Separator(RBrace),
             start: Position {
+                file: FileId::ANONYMOUS,
                 line: input[2],
                 line_num: 2,
+                line_start: 15,
                 col: 1,
             },
             lexeme: "}",
+            doc: None,
         };
 
         let expected_output = vec![if_, while_, else_, left, right];
@@ -270,4 +746,669 @@ mod tests {
         }
         .run(&tokenizer);
     }
+
+    /// Check that bad input produces the right errors, instead of panicking.
+    #[test]
+    fn errors() {
+        let tokenizer = Tokenizer::new();
+
+        for (input, expected_col) in [
+            (vec!["x = 2147483649;"], 4),
+            (vec!["x = 99999999999;"], 4),
+            (vec!["c = '';"], 4),
+            (vec!["c = 'ab';"], 4),
+            (vec!["s = \"ab\\qc\";"], 7),
+            (vec!["a # b"], 2),
+            (vec!["a", "  /* no end", "", "*"], 2),
+            // `/**/` is a whole comment, even though it starts like an unclosed doc comment.
+            (vec!["/**/ x /* no end"], 7),
+        ] {
+            let (_, errors) = tokenizer.tokenize_all(input.iter().copied());
+            assert_eq!(errors.len(), 1, "{:?}", input);
+            assert_eq!(errors[0].start().col, expected_col, "{:?}", input);
+        }
+    }
+
+    /// Check which variants of `TokenError` are produced.
+    #[test]
+    fn error_variants() {
+        use tokens::EscapeError;
+        use TokenError::*;
+
+        let tokenizer = Tokenizer::new();
+        let first_err = |line| {
+            tokenizer
+                .tokenize(std::iter::once(line))
+                .find_map(Result::err)
+                .unwrap()
+        };
+
+        assert!(matches!(first_err("2147483649"), IntTooLarge { .. }));
+        assert!(matches!(first_err("''"), EmptyCharLiteral { .. }));
+        assert!(matches!(first_err("'ab'"), CharLiteralTooLong { .. }));
+        assert!(matches!(
+            first_err("'\\9'"),
+            BadEscape {
+                error: EscapeError::NotOctal('9'),
+                ..
+            }
+        ));
+        assert!(matches!(first_err("\"abc"), NotAToken { .. }));
+        assert!(matches!(first_err("/* abc"), UnclosedComment { .. }));
+        assert!(matches!(first_err(r"// \u00G1"), BadUnicodeEscape { .. }));
+        assert!(matches!(first_err(r#""\u004""#), BadUnicodeEscape { .. }));
+
+        // 2^31 is fine, as long as it's preceded by unary minus.
+        assert!(matches!(first_err("2147483648"), MinIntNotNegated { .. }));
+        assert!(tokenizer
+            .tokenize(std::iter::once("-2147483648"))
+            .all(|t| t.is_ok()));
+    }
+
+    /// Check that scanning resumes after an error, so that every error is reported.
+    #[test]
+    fn error_recovery() {
+        use token_types::Keyword::Int;
+        use token_types::Operator::Plus;
+        use Token::Identifier;
+
+        let tokenizer = Tokenizer::new();
+
+        let input = [
+            "int # x = 'ab' + 99999999999 @y;",
+            "x = \"unclosed;",
+            "int y = \"\\q\" + z;",
+            "int z; /* unclosed",
+            "int w;",
+        ];
+        let (tokens, errors) = tokenizer.tokenize_all(input.iter().copied());
+
+        let tokens: Vec<_> = tokens.into_iter().map(|t| t.val).collect();
+        let expected = vec![
+            Keyword(Int),
+            Identifier("x".into()),
+            Operator(Assign),
+            Operator(Plus),
+            Separator(Semicolon),
+            Identifier("x".into()),
+            Operator(Assign),
+            Keyword(Int),
+            Identifier("y".into()),
+            Operator(Assign),
+            Operator(Plus),
+            Identifier("z".into()),
+            Separator(Semicolon),
+            Keyword(Int),
+            Identifier("z".into()),
+            Separator(Semicolon),
+        ];
+        assert_eq!(expected, tokens);
+
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|e| (e.start().line_num, e.start().col))
+            .collect();
+        let expected = vec![(0, 4), (0, 10), (0, 17), (0, 29), (1, 4), (2, 9), (3, 7)];
+        assert_eq!(expected, errors);
+    }
+
+    /// A malformed unicode escape is an error anywhere, even in a comment (JLS 3.3). It's reported
+    /// once, and scanning carries on after it.
+    #[test]
+    fn malformed_unicode_escapes() {
+        use token_types::Keyword::Int;
+        use Token::Identifier;
+
+        let tokenizer = Tokenizer::new();
+
+        let input = [
+            r"int x; // \u00G1 \u0",
+            r"/* \u */ int y = \u12;",
+            r"// \\u00G1 is fine",
+        ];
+        let (tokens, errors) = tokenizer.tokenize_all(input.iter().copied());
+
+        let tokens: Vec<_> = tokens.into_iter().map(|t| t.val).collect();
+        let expected = vec![
+            Keyword(Int),
+            Identifier("x".into()),
+            Separator(Semicolon),
+            Keyword(Int),
+            Identifier("y".into()),
+            Operator(Assign),
+            Separator(Semicolon),
+        ];
+        assert_eq!(expected, tokens);
+
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                TokenError::BadUnicodeEscape { start, lexeme } => {
+                    (start.line_num, start.col, *lexeme)
+                }
+                e => panic!("unexpected error {}", e),
+            })
+            .collect();
+        let expected = vec![(0, 10, r"\u00G"), (1, 3, r"\u"), (1, 17, r"\u12;")];
+        assert_eq!(expected, errors);
+
+        let (_, errors) = tokenizer.tokenize_all(std::iter::once(r"/* \u00G1 */"));
+        let expected = "\
+error: malformed unicode escape `\\u00G`
+ --> A.java:1:4
+  |
+1 | /* \\u00G1 */
+  |    ^^^^^
+  |
+  = note: `\\u` has to be followed by four hex digits, even in a comment
+";
+        let actual = errors[0].diagnostic().render("A.java", ColorMode::Plain);
+        assert_eq!(expected, actual);
+    }
+
+    /// Render a multi-line error for the user.
+    #[test]
+    fn unclosed_comment_diagnostic() {
+        let tokenizer = Tokenizer::new();
+
+        let input = ["int x; /* oops", "", "int y;"];
+        let (_, errors) = tokenizer.tokenize_all(input.iter().copied());
+        assert_eq!(errors.len(), 1);
+
+        let expected = "\
+error: unclosed comment; expected `*/`
+ --> A.java:1:8
+  |
+1 |   int x; /* oops
+  |  ________^
+...
+3 | | int y;
+  | |_______^ comment runs to the end of the file
+";
+        let actual = errors[0].diagnostic().render("A.java", ColorMode::Plain);
+        assert_eq!(expected, actual);
+    }
+
+    /// Java-only tokens are reported, unless the dialect is Java.
+    #[test]
+    fn check_supported() {
+        let src = "x >>= 1; do { i++; } while (a ? b : c); int y = ~x & z | w;";
+        let reported = |dialect| -> Vec<_> {
+            let tokenizer = Tokenizer::with_dialect(dialect);
+            let (tokens, errors) = tokenizer.tokenize_all(Text(src));
+            assert!(errors.is_empty());
+            tokenizer
+                .check_supported(&tokens)
+                .iter()
+                .map(|e| (e.start().col, e.to_string()))
+                .collect()
+        };
+
+        let expected = vec![
+            (2, "`>>=` is not supported in Joos 1W"),
+            (9, "`do` is not supported in Joos 1W"),
+            (15, "`++` is not supported in Joos 1W"),
+            (30, "`?` is not supported in Joos 1W"),
+            (34, "`:` is not supported in Joos 1W"),
+            (48, "`~` is not supported in Joos 1W"),
+        ];
+        let expected: Vec<_> = expected.into_iter().map(|(c, m)| (c, m.into())).collect();
+        assert_eq!(expected, reported(Dialect::Joos1W));
+        assert_eq!(6, reported(Dialect::Joos2).len());
+        assert!(reported(Dialect::Java1_3).is_empty());
+
+        let tokenizer = Tokenizer::new();
+        let (tokens, _) = tokenizer.tokenize_all(Text("x >>= 1;"));
+        let expected = "\
+error: `>>=` is not supported in Joos 1W
+ --> A.java:1:3
+  |
+1 | x >>= 1;
+  |   ^^^ only in Java
+";
+        let errors = tokenizer.check_supported(&tokens);
+        let actual = errors[0].diagnostic().render("A.java", ColorMode::Plain);
+        assert_eq!(expected, actual);
+    }
+
+    /// Unicode escapes are translated before lexing, and non-ASCII characters are only allowed in
+    /// comments and literals.
+    #[test]
+    fn unicode() {
+        use token_types::Keyword::Int;
+        use token_types::Literal::{Char, StringLit};
+        use Token::{Identifier, Literal};
+
+        let tokenizer = Tokenizer::new();
+
+        let input = [
+            r"\u0069nt \u0078 = 'é'; // é",
+            r"/* ü */ '\u005c\u0027';",
+            r#""h\u00e9llo" "héllo""#,
+        ];
+        let actual: Vec<_> = tokenizer
+            .tokenize(input.iter().copied())
+            .map(|t| t.unwrap())
+            .collect();
+        let expected = vec![
+            (Keyword(Int), r"\u0069nt"),
+            // Identifiers are translated too, so `\u0078` is the same name as `x`.
+            (Identifier("x".into()), r"\u0078"),
+            (Operator(Assign), "="),
+            (Literal(Char('é')), "'é'"),
+            (Separator(Semicolon), ";"),
+            (Literal(Char('\'')), r"'\u005c\u0027'"),
+            (Separator(Semicolon), ";"),
+            (Literal(StringLit(String::from("héllo"))), r#""h\u00e9llo""#),
+            (Literal(StringLit(String::from("héllo"))), r#""héllo""#),
+        ];
+        let actual: Vec<_> = actual.iter().map(|t| (t.val.clone(), t.lexeme)).collect();
+        assert_eq!(expected, actual);
+
+        // Columns are byte offsets into the original line.
+        let (tokens, _) = tokenizer.tokenize_all(input[..1].iter().copied());
+        let cols: Vec<_> = tokens.iter().map(|t| t.start.col).collect();
+        assert_eq!(vec![0, 9, 16, 18, 22], cols);
+
+        let (_, errors) = tokenizer.tokenize_all(["int é = 1; \"é\\q\"; // ok: é"].iter().copied());
+        let expected = vec![
+            TokenError::NonAsciiChar {
+                c: 'é',
+                pos: Position {
+                    file: FileId::ANONYMOUS,
+                    line: "int é = 1; \"é\\q\"; // ok: é",
+                    line_num: 0,
+                    line_start: 0,
+                    col: 4,
+                },
+            },
+            TokenError::BadEscape {
+                pos: Position {
+                    file: FileId::ANONYMOUS,
+                    line: "int é = 1; \"é\\q\"; // ok: é",
+                    line_num: 0,
+                    line_start: 0,
+                    col: 15,
+                },
+                error: tokens::EscapeError::Unknown('q'),
+            },
+        ];
+        assert_eq!(expected, errors);
+    }
+
+    /// Tokens from different files can be traced back to the right file.
+    #[test]
+    fn spans_across_files() {
+        let tokenizer = Tokenizer::new();
+
+        let mut map = SourceMap::new();
+        let a = map.add_file("A.java", "class A {\n  int x; // hi\n}\n");
+        let b = map.add_file("B.java", "/* B */\r\nclass B {}");
+
+        for id in [a, b] {
+            for elem in tokenizer.tokenize_keep_comments(map.file(id)) {
+                let elem = elem.unwrap();
+                let span = elem.span();
+                assert_eq!(span.file, id);
+
+                let start = map.start(span);
+                assert_eq!(
+                    (start.line_num, start.col),
+                    (elem.start().line_num, elem.start().col)
+                );
+
+                if let TokenOrComment::Token(t) = &elem {
+                    assert_eq!(map.snippet(span), t.lexeme);
+                }
+            }
+        }
+
+        let (tokens, _) = tokenizer.tokenize_all(map.file(a));
+        assert_eq!("A.java:2:7", map.location(tokens[4].span()));
+        let (tokens, _) = tokenizer.tokenize_all(map.file(b));
+        assert_eq!("B.java:2:7", map.location(tokens[1].span()));
+
+        let comments: Vec<_> = tokenizer
+            .tokenize_keep_comments(map.file(a))
+            .chain(tokenizer.tokenize_keep_comments(map.file(b)))
+            .filter(|elem| !matches!(elem, Ok(TokenOrComment::Token(_))))
+            .map(|elem| map.snippet(elem.unwrap().span()))
+            .collect();
+        assert_eq!(vec!["// hi", "/* B */"], comments);
+    }
+
+    /// Each kind of comment comes out separately, with its text. Javadoc is attached to the next
+    /// token.
+    #[test]
+    fn comments() {
+        let tokenizer = Tokenizer::new();
+        let src = "/** A. */\n/* not doc */ public class A {\r\n  /**\r\n   * x\r\n   */ int x; // hi\n/**/}";
+
+        let actual: Vec<_> = tokenizer
+            .tokenize_keep_comments(Text(src))
+            .filter_map(|elem| {
+                let elem = elem.unwrap();
+                let kind = match &elem {
+                    TokenOrComment::Token(_) => return None,
+                    TokenOrComment::LineComment(_) => "line",
+                    TokenOrComment::BlockComment(_) => "block",
+                    TokenOrComment::DocComment(_) => "doc",
+                };
+                let c = elem.comment().unwrap();
+                Some((kind, c.text.to_string(), c.body().to_string()))
+            })
+            .collect();
+        let expected = vec![
+            ("doc", "/** A. */", " A. "),
+            ("block", "/* not doc */", " not doc "),
+            ("doc", "/**\n   * x\n   */", "\n   * x\n   "),
+            ("line", "// hi", " hi"),
+            ("block", "/**/", ""),
+        ];
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(kind, text, body)| (kind, String::from(text), String::from(body)))
+            .collect();
+        assert_eq!(expected, actual);
+
+        let docs: Vec<_> = tokenizer
+            .tokenize(Text(src))
+            .map(|t| t.unwrap())
+            .filter_map(|t| Some((t.lexeme, t.doc?.text.into_owned())))
+            .collect();
+        let expected = vec![
+            ("public", String::from("/** A. */")),
+            ("int", String::from("/**\n   * x\n   */")),
+        ];
+        assert_eq!(expected, docs);
+    }
+
+    /// Whole-string input, with a mix of line terminators.
+    #[test]
+    fn line_terminators() {
+        let tokenizer = Tokenizer::new();
+        let src = "int x; // a\rint y;\r\n// b\r\n/* c\r\r */ z\n";
+
+        let actual: Vec<_> = tokenizer
+            .tokenize_keep_comments(Text(src))
+            .map(|elem| {
+                let elem = elem.unwrap();
+                let span = elem.span();
+                let start = elem.start();
+                (
+                    &src[span.lo as usize..span.hi as usize],
+                    start.line_num,
+                    start.col,
+                )
+            })
+            .collect();
+        let expected = vec![
+            ("int", 0, 0),
+            ("x", 0, 4),
+            (";", 0, 5),
+            ("// a", 0, 7),
+            ("int", 1, 0),
+            ("y", 1, 4),
+            (";", 1, 5),
+            ("// b", 2, 0),
+            ("/* c\r\r */", 3, 0),
+            ("z", 5, 4),
+        ];
+        assert_eq!(expected, actual);
+
+        // The same thing, but from a `SourceFile`.
+        let mut map = SourceMap::new();
+        let id = map.read_file("A.java", src.as_bytes()).unwrap();
+        let from_file: Vec<_> = tokenizer
+            .tokenize(map.file(id))
+            .map(|t| t.unwrap().span())
+            .collect();
+        let from_text: Vec<_> = tokenizer
+            .tokenize(Text(src))
+            .map(|t| t.unwrap().span())
+            .collect();
+        assert_eq!(
+            from_text.iter().map(|s| (s.lo, s.hi)).collect::<Vec<_>>(),
+            from_file.iter().map(|s| (s.lo, s.hi)).collect::<Vec<_>>()
+        );
+        assert_eq!("A.java:6:5", map.location(from_file[6]));
+    }
+
+    /// Trivia is split between tokens at line ends, and the source comes back exactly.
+    #[test]
+    fn lossless() {
+        use lossless::TriviaKind::*;
+
+        let tokenizer = Tokenizer::new();
+        let src = "\t/** A. */ int x; // a\r\n\x0c\r\n  /* b */y\\u0020;\r  \n";
+        let file = tokenizer.tokenize_lossless(Text(src)).unwrap();
+        assert_eq!(src, file.to_string());
+
+        fn trivia<'a>(trivia: &[lossless::Trivia<'a>]) -> Vec<(lossless::TriviaKind, &'a str)> {
+            trivia.iter().map(|t| (t.kind, t.text)).collect()
+        }
+        let actual: Vec<_> = file
+            .tokens
+            .iter()
+            .map(|t| (trivia(&t.leading), t.token.lexeme, trivia(&t.trailing)))
+            .collect();
+        let expected = vec![
+            (
+                vec![
+                    (Whitespace, "\t"),
+                    (DocComment, "/** A. */"),
+                    (Whitespace, " "),
+                ],
+                "int",
+                vec![(Whitespace, " ")],
+            ),
+            (vec![], "x", vec![]),
+            (
+                vec![],
+                ";",
+                vec![(Whitespace, " "), (LineComment, "// a"), (Newline, "\r\n")],
+            ),
+            (
+                vec![
+                    (Whitespace, "\x0c"),
+                    (Newline, "\r\n"),
+                    (Whitespace, "  "),
+                    (BlockComment, "/* b */"),
+                ],
+                "y",
+                vec![(Whitespace, "\\u0020")],
+            ),
+            (vec![], ";", vec![(Newline, "\r")]),
+        ];
+        assert_eq!(expected, actual);
+        assert_eq!(vec![(Whitespace, "  "), (Newline, "\n")], trivia(&file.end));
+
+        // Spans match the text.
+        let spans = file
+            .tokens
+            .iter()
+            .flat_map(|t| t.leading.iter().chain(&t.trailing));
+        for t in spans.chain(&file.end) {
+            assert_eq!(t.text, &src[t.span.lo as usize..t.span.hi as usize]);
+        }
+
+        // No tokens at all.
+        for src in ["", "\n", " // a", "/* a */\r\n\r\n"] {
+            let file = tokenizer.tokenize_lossless(Text(src)).unwrap();
+            assert!(file.tokens.is_empty());
+            assert_eq!(src, file.to_string());
+        }
+
+        assert_eq!(
+            1,
+            tokenizer
+                .tokenize_lossless(Text("x # y"))
+                .unwrap_err()
+                .len()
+        );
+    }
+
+    #[test]
+    fn simple_string_lit() {
+        TestCase {
+            input: vec!["\"asdf\""],
+            expected_output: vec![Literal(StringLit(String::from("asdf")))],
+        }
+        .run(&Tokenizer::new())
+    }
+
+    #[test]
+    fn string_lit_escape_quote() {
+        let tokenizer = Tokenizer::new();
+        for (input, expected_output) in [
+            (
+                vec!["\"asdf\\\"asdf\""],
+                vec![Literal(StringLit(String::from("asdf\"asdf")))],
+            ),
+            (
+                vec!["  \"abcabc\\\\abc\"  "],
+                vec![Literal(StringLit(String::from("abcabc\\abc")))],
+            ),
+        ] {
+            TestCase {
+                input,
+                expected_output,
+            }
+            .run(&tokenizer)
+        }
+    }
+
+    /// Minimization should actually shrink the DFA.
+    #[test]
+    fn dfa_stats() {
+        let stats = Tokenizer::build(Dialect::Joos1W).dfa_stats();
+        assert!(
+            stats.minimized_states < stats.raw_states.unwrap(),
+            "{:?}",
+            stats
+        );
+
+        let loaded = Tokenizer::new().dfa_stats();
+        assert_eq!(None, loaded.raw_states);
+        assert_eq!(stats.minimized_states, loaded.minimized_states);
+        assert_eq!(stats.minimized_transitions, loaded.minimized_transitions);
+    }
+
+    /// The checked-in tables must match a fresh construction of the DFAs.
+    ///
+    /// Set `REGENERATE_TABLES=1` to overwrite the tables instead.
+    #[test]
+    fn tables_up_to_date() {
+        for (dialect, file) in [
+            (Dialect::Joos1W, "joos_1w.dfa"),
+            (Dialect::Java1_3, "java_1_3.dfa"),
+        ] {
+            let fresh = Tokenizer::build(dialect);
+            let table = fresh.dfa_table();
+
+            if std::env::var_os("REGENERATE_TABLES").is_some() {
+                let path = format!("{}/src/tokenizer/{}", env!("CARGO_MANIFEST_DIR"), file);
+                std::fs::write(path, &table).unwrap();
+                continue;
+            }
+
+            assert!(
+                table == dialect.table(),
+                "The {} table is out of date; run `REGENERATE_TABLES=1 cargo test tables`",
+                file
+            );
+            assert_eq!(table, Tokenizer::with_dialect(dialect).dfa_table());
+        }
+    }
+
+    /// Java 1.3 has more literals. In Joos, they come out as several tokens, or errors.
+    #[test]
+    fn dialects() {
+        use token_types::Literal::*;
+        use tokens::Token::{Identifier, Literal};
+
+        fn tokens<'a>(tokenizer: &'a Tokenizer, src: &'a str) -> Option<Vec<tokens::Token<'a>>> {
+            tokenizer
+                .tokenize(Text(src))
+                .map(|t| t.ok().map(|t| t.val))
+                .collect()
+        }
+
+        let tokenizers = Dialect::ALL.map(Tokenizer::with_dialect);
+        let lit = |l| Some(vec![Literal(l)]);
+        let s = String::from;
+        for (src, java) in [
+            ("0x1F", lit(HexInt(s("0x1F")))),
+            ("0XaBc", lit(HexInt(s("0XaBc")))),
+            ("017", lit(OctalInt(s("017")))),
+            ("0", lit(Int(s("0")))),
+            ("10L", lit(Long(s("10")))),
+            ("0x1Fl", lit(Long(s("0x1F")))),
+            ("1.5e3f", lit(Float(s("1.5e3")))),
+            ("1F", lit(Float(s("1")))),
+            (".5", lit(Double(s(".5")))),
+            ("1.", lit(Double(s("1.")))),
+            ("1e-3", lit(Double(s("1e-3")))),
+            ("2d", lit(Double(s("2")))),
+            ("0x1D", lit(HexInt(s("0x1D")))),
+            ("1.5", lit(Double(s("1.5")))),
+            (
+                "a.b",
+                Some(vec![
+                    Identifier("a".into()),
+                    tokens::Token::Separator(token_types::Separator::Dot),
+                    Identifier("b".into()),
+                ]),
+            ),
+        ] {
+            let tokens = |tokenizer| tokens(tokenizer, src);
+            assert_eq!(java, tokens(&tokenizers[2]), "{}", src);
+
+            let joos = tokens(&tokenizers[0]);
+            assert_eq!(joos, tokens(&tokenizers[1]));
+            if src != "0" && src != "a.b" {
+                assert_ne!(java, joos, "{}", src);
+            }
+        }
+    }
+
+    #[test]
+    fn kinds() {
+        let tokenizer = Tokenizer::with_dialect(Dialect::Java1_3);
+        let src = "if (x <<= 'c') return null + \"s\" + true + 0x1 + 2L + 1.5f + 07 + 3 + .5;";
+        let names: Vec<_> = tokenizer
+            .tokenize(Text(src))
+            .map(|t| t.unwrap().val.kind().name())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "IF",
+                "LPAREN",
+                "ID",
+                "LSHIFT_EQ",
+                "CHAR_LITERAL",
+                "RPAREN",
+                "RETURN",
+                "NULL_LITERAL",
+                "PLUS",
+                "STRING_LITERAL",
+                "PLUS",
+                "BOOLEAN_LITERAL",
+                "PLUS",
+                "HEX_LITERAL",
+                "PLUS",
+                "LONG_LITERAL",
+                "PLUS",
+                "FLOAT_LITERAL",
+                "PLUS",
+                "OCTAL_LITERAL",
+                "PLUS",
+                "INTEGER_LITERAL",
+                "PLUS",
+                "DOUBLE_LITERAL",
+                "SEMICOLON",
+            ]
+        );
+    }
 }
@@ -1,185 +1,379 @@
+use crate::tokenizer::lexer::Lexeme;
 use crate::tokenizer::states::{AcceptedStateLabel, Symbol};
-use crate::tokenizer::token_types::Literal::{self, Char, Int, StringLit};
-use crate::tokenizer::tokens::{Token, TokenInfo, TokenOrComment};
-use crate::tokenizer::Position;
-use key_pair::KeyPair;
+use crate::tokenizer::token_types::Literal::{
+    self, Bool, Char, Double, Float, HexInt, Int, Long, Null, OctalInt, StringLit,
+};
+use crate::tokenizer::token_types::TokenKind;
+use crate::tokenizer::tokens::{Comment, Token, TokenError, TokenInfo, TokenOrComment};
+use crate::tokenizer::{unicode, Position};
+pub use compiled::CompiledDFA;
 use std::collections::HashMap as Map;
-use std::fmt::Debug;
-use std::hash::Hash;
-use std::iter;
+use std::iter::{self, Peekable};
 
-mod key_pair;
+mod compiled;
+pub(super) mod key_pair;
 mod string_escapes;
+mod table;
 
 /// A DFA used for tokenizing an input stream of symbols into an output stream of tokens.
+///
+/// Each accepted state is labelled with an `L`, e.g. the token type it accepts.
+///
+/// This form is easy to build and inspect. To actually run it, `compile` it first.
 #[derive(Debug)]
-pub struct DFA<S> {
+#[allow(clippy::upper_case_acronyms)]
+pub struct DFA<S, L> {
     pub init: S,
-    pub accepted: Map<S, AcceptedStateLabel>,
+    pub accepted: Map<S, L>,
     /// If a keypair doesn't exist, this means a transition to an implicit "dead state" which isn't
     /// accepted.
     pub delta: Map<(S, Symbol), S>,
 }
 
+/// A token found by max munch: the longest prefix of the input accepted by the DFA.
+#[derive(Debug)]
+pub struct Match<'a, L> {
+    /// The label of the accepted state.
+    pub label: &'a L,
+    pub start: Position<'a>,
+    /// Inclusive! May be on a later line than `start`.
+    pub end_inclusive: Position<'a>,
+}
+
+/// One step of `CompiledDFA::scan`: a match or an error, and how much of the input was looked at
+/// to find it.
+#[derive(Debug)]
+pub struct Scanned<'a, L> {
+    /// Where the step started.
+    pub start: Position<'a>,
+    pub result: Result<Match<'a, L>, TokenError<'a>>,
+    /// The byte offset just past the last symbol the scanner looked at. The result only depends on
+    /// the input from `start` up to here. `usize::MAX` if the scanner ran into the end of the
+    /// input, since then appending anything could change the result.
+    pub read_to: usize,
+}
+
+/// Which states of a `CompiledDFA` have to do with comments. See `CompiledDFA::max_munch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentStates {
+    /// Is the state accepted, with a label for a comment?
+    accepted: Vec<bool>,
+    /// Can the state still reach the end of a comment? If the input runs out in such a state, the
+    /// comment was left unclosed.
+    unclosed: Vec<bool>,
+}
+
 /// Return value for the `max_munch` method.
-enum LongestMatch<'a> {
-    Match(TokenOrComment<'a>),
-    Whitespace,
-    NoMatch,
+enum LongestMatch<'a, L> {
+    Match(Result<Match<'a, L>, TokenError<'a>>),
+    NoMatch {
+        /// The position on which the DFA got stuck; or the last position in the stream, if the
+        /// stream dried up first.
+        end: Position<'a>,
+        /// The first malformed unicode escape the DFA went over, if any. (It stands for a
+        /// backslash.)
+        bad_escape: Option<TokenError<'a>>,
+    },
 }
 
-impl<S: Eq + Hash + Debug> DFA<S> {
+/// How far the scanner looked: just past `last`, or through the end of the input if it ran out.
+fn read_to(last: Position, eof: bool) -> usize {
+    if eof {
+        usize::MAX
+    } else {
+        // The "newline" position has no width, but it stands for a line terminator.
+        last.offset() + last.width().max(1)
+    }
+}
+
+impl<L> CompiledDFA<L> {
     /// Tokenize the input stream by running "max munch" in a loop.
-    // todo: report errors.
+    ///
+    /// `comments` says which states have to do with comments, so that unclosed comments can be
+    /// reported. (See `max_munch`.)
+    ///
+    /// Errors are reported in-line. After each error, we resynchronize (see `resync`) and keep
+    /// scanning, so that a single run can report every lexical error in the input.
     pub fn tokenize<'a>(
         &'a self,
         positions: impl Iterator<Item = Position<'a>> + Clone + 'a,
-    ) -> impl Iterator<Item = TokenOrComment> + 'a {
-        let mut positions = positions.peekable();
-
-        iter::from_fn(move || loop {
-            match positions.peek().copied() {
-                // The stream dried up; terminate.
-                None => return None,
-
-                Some(pos) => {
-                    match self.max_munch(pos, &mut positions) {
-                        LongestMatch::Match(t) => return Some(t),
+        comments: &'a CommentStates,
+    ) -> impl Iterator<Item = Result<Match<'a, L>, TokenError<'a>>> + 'a {
+        self.scan(positions, comments).map(|step| step.result)
+    }
 
-                        // Silently ignore, keep munching.
-                        LongestMatch::Whitespace => continue,
+    /// Like `tokenize`, but also report where each step started, and how far ahead it looked.
+    ///
+    /// Each step starts right where the previous one left off, so together they cover the whole
+    /// input.
+    pub fn scan<'a>(
+        &'a self,
+        positions: impl Iterator<Item = Position<'a>> + Clone + 'a,
+        comments: &'a CommentStates,
+    ) -> impl Iterator<Item = Scanned<'a, L>> + 'a {
+        let mut positions = positions.peekable();
 
-                        LongestMatch::NoMatch => {
-                            // todo: handle errors more gracefully.
-                            panic!("Failed to tokenize at {:?}", pos);
-                        }
-                    }
+        iter::from_fn(move || {
+            // If the stream dried up, terminate.
+            let start = positions.peek().copied()?;
+
+            let (result, read_to) = match self.max_munch(start, &mut positions, comments) {
+                (LongestMatch::Match(result), read_to) => (result, read_to),
+
+                (LongestMatch::NoMatch { end, bad_escape }, read_to) => {
+                    let read_to = resync(end, &mut positions).unwrap_or(read_to);
+
+                    // If we went over a malformed unicode escape, or got stuck on a non-ASCII
+                    // character, that's the real culprit.
+                    let err = if let Some(err) = bad_escape {
+                        err
+                    } else if end.symbol() == Symbol::NON_ASCII {
+                        let c = end.source_char();
+                        TokenError::NonAsciiChar { c, pos: end }
+                    } else {
+                        TokenError::NotAToken { start, end }
+                    };
+                    (Err(err), read_to)
                 }
-            }
+            };
+            Some(Scanned {
+                start,
+                result,
+                read_to,
+            })
         })
     }
 
-    /// Return a token corresponding to the longest matching prefix of the stream.
+    /// Find the states that have to do with comments, given which labels are for comments.
+    pub fn comment_states(&self, is_comment: impl Fn(&L) -> bool) -> CommentStates {
+        let accepted = (0..self.num_states() as u32)
+            .map(|s| self.label(s).is_some_and(&is_comment))
+            .collect();
+        let unclosed = self.can_reach(is_comment);
+        CommentStates { accepted, unclosed }
+    }
+
+    /// Return a token corresponding to the longest matching prefix of the stream, along with how
+    /// far the scanner looked (see `Scanned::read_to`).
     ///
     /// Consumes up to and including the last symbol of that token.
     fn max_munch<'a>(
         &'a self,
         start: Position<'a>,
         positions: &mut (impl Iterator<Item = Position<'a>> + Clone),
-    ) -> LongestMatch {
-        if self.accepted.contains_key(&self.init) {
-            panic!("Empty matches unsupported; please fix your DFA: {:?}", self);
-        }
-
+        comments: &CommentStates,
+    ) -> (LongestMatch<'a, L>, usize) {
         // Keep track of the longest match, and the positions after it.
-        let mut longest_match = LongestMatch::NoMatch;
+        let mut longest_match = None;
         let mut unused_symbols = positions.clone();
+        let mut end = start;
+        let mut eof = true;
+        // The first malformed unicode escape so far, and the first one in the longest match.
+        let mut bad_escape = None;
+        let mut bad_escape_in_match = None;
 
-        let mut state = &self.init;
+        let mut state = self.init;
         while let Some(pos) = positions.next() {
-            let key = (state, &pos.symbol());
-            state = match self.delta.get(&key as &dyn KeyPair<_, _>) {
+            end = pos;
+            if bad_escape.is_none() {
+                bad_escape = unicode::decode(pos).err();
+            }
+
+            state = match self.step(state, pos.symbol()) {
                 Some(next) => next,
                 // Implicit "dead" state, stop scanning.
-                None => break,
+                None => {
+                    eof = false;
+                    break;
+                }
             };
 
-            if let Some(label) = self.accepted.get(state) {
+            if let Some(label) = self.label(state) {
                 unused_symbols = positions.clone();
-                longest_match = match label {
-                    AcceptedStateLabel::TokenType { type_ } => {
-                        LongestMatch::Match(TokenOrComment::Token(token_info(type_, start, pos)))
-                    }
-                    AcceptedStateLabel::LineComment => {
-                        LongestMatch::Match(TokenOrComment::LineComment { start })
-                    }
-                    AcceptedStateLabel::StarComment | AcceptedStateLabel::JavadocComment => {
-                        LongestMatch::Match(TokenOrComment::StarComment {
-                            start,
-                            end_inclusive: pos,
-                        })
-                    }
-                    AcceptedStateLabel::Whitespace => LongestMatch::Whitespace,
-                };
+                longest_match = Some((label, pos, state));
+                bad_escape_in_match = bad_escape.clone();
             }
         }
 
+        // A comment that runs into the end of the stream is an error, even if a shorter prefix
+        // (e.g. the `/` operator) would match. But if that prefix is itself a whole comment (e.g.
+        // `/**/` at the start of `/**/ x`), it's fine.
+        let whole_stream_matched = matches!(longest_match, Some((_, e, _)) if e == end);
+        let comment_matched =
+            matches!(longest_match, Some((_, _, s)) if comments.accepted[s as usize]);
+        let read_to = read_to(end, eof);
+        if eof && !whole_stream_matched && !comment_matched && comments.unclosed[state as usize] {
+            let err = TokenError::UnclosedComment { start, end };
+            return (LongestMatch::Match(Err(err)), read_to);
+        }
+
+        let (label, end, _) = match longest_match {
+            Some(m) => m,
+            None => return (LongestMatch::NoMatch { end, bad_escape }, read_to),
+        };
+
         // Reset `positions` to reflect which symbols were actually consumed by the longest match.
         *positions = unused_symbols;
 
-        longest_match
+        // A malformed unicode escape spoils the whole match, even if it's a comment.
+        if let Some(err) = bad_escape_in_match {
+            return (LongestMatch::Match(Err(err)), read_to);
+        }
+
+        let m = Match {
+            label,
+            start,
+            end_inclusive: end,
+        };
+        (LongestMatch::Match(Ok(m)), read_to)
+    }
+}
+
+/// Turn a lexeme of a Joos token type into a token or comment.
+///
+/// Returns `None` for whitespace.
+pub fn token_or_comment<'a>(
+    label: &AcceptedStateLabel,
+    lexeme: Lexeme<'a>,
+) -> Option<Result<TokenOrComment<'a>, TokenError<'a>>> {
+    let comment = Comment {
+        start: lexeme.start,
+        end_inclusive: lexeme.end_inclusive,
+        text: lexeme.text,
+    };
+    Some(Ok(match label {
+        AcceptedStateLabel::TokenType { kind } => {
+            match token_info(*kind, lexeme.start, lexeme.end_inclusive) {
+                Ok(t) => TokenOrComment::Token(t),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        AcceptedStateLabel::LineComment => TokenOrComment::LineComment(comment),
+        AcceptedStateLabel::StarComment => TokenOrComment::BlockComment(comment),
+        AcceptedStateLabel::JavadocComment => TokenOrComment::DocComment(comment),
+        AcceptedStateLabel::Whitespace => return None,
+    }))
+}
+
+/// Skip past the remains of some input that failed to tokenize, so that scanning can resume.
+///
+/// Our policy is to skip everything up to the next whitespace or separator character. These can't
+/// appear inside an identifier, keyword, or int literal, so we're unlikely to resume scanning in
+/// the middle of some garbled token and report a bogus follow-up error.
+///
+/// `stuck` is the position the scanner got stuck on, which `positions` must already have
+/// consumed. If that was whitespace or a separator, there's nothing more to skip.
+///
+/// Returns how far this looked ahead (see `Scanned::read_to`), if it looked at anything.
+fn resync<'a, I>(stuck: Position<'a>, positions: &mut Peekable<I>) -> Option<usize>
+where
+    I: Iterator<Item = Position<'a>>,
+{
+    if is_delimiter(stuck.symbol()) {
+        return None;
+    }
+
+    while let Some(pos) = positions.peek() {
+        if is_delimiter(pos.symbol()) {
+            return Some(read_to(*pos, false));
+        }
+        positions.next();
+    }
+    Some(read_to(stuck, true))
+}
+
+/// Is this symbol whitespace or a separator?
+fn is_delimiter(sym: Symbol) -> bool {
+    matches!(
+        sym.to_char(),
+        ' ' | '\t' | '\x0C' | '\n' | '(' | ')' | '{' | '}' | '[' | ']' | ';' | ',' | '.'
+    )
+}
+
+/// The text of a numeric literal, with unicode escapes translated, minus its type suffix if any.
+fn without_suffix(lexeme: &str, suffix: &[char]) -> String {
+    let mut text = unicode::translate(lexeme).into_owned();
+    if text.ends_with(suffix) {
+        text.pop();
     }
+    text
 }
 
-/// Create TokenInfo from a token type.
+/// Create TokenInfo from a token kind.
 ///
 /// Note that `start` and `end` are both inclusive!!!
-fn token_info<'a>(type_: &Token<'static>, start: Position<'a>, end: Position<'a>) -> TokenInfo<'a> {
-    // Note the inclusive range.
-    let lexeme = &start.line[start.col..=end.col];
+fn token_info<'a>(
+    kind: TokenKind,
+    start: Position<'a>,
+    end: Position<'a>,
+) -> Result<TokenInfo<'a>, TokenError<'a>> {
+    // Note that `end` is inclusive, and may be more than one byte wide.
+    let lexeme = &start.line[start.col..end.col + end.width()];
 
     // Fill in the guts of the token, if applicable.
-    let val = match type_ {
-        Token::Identifier(_) => Token::Identifier(lexeme),
-        Token::Literal(lit) => Token::Literal(match lit {
-            Int(_) => {
-                // Note that in Joos 1W, all int literals are `int` type, since there is no
-                // `unsigned` in Java, and no `long` in Joos 1W.
-                let n: Result<u32, _> = lexeme.parse();
-
-                // todo handle errors gracefully
-                let n = n.expect("Can't parse int; probably too big.");
-                assert!(n <= 2u32.pow(31));
-
-                Int(n)
-            }
-            Char(_) => make_char_literal(lexeme),
-            StringLit(_) => {
-                // Strip quotes.
-                debug_assert_eq!(&lexeme[..1], "\"");
-                debug_assert_eq!(&lexeme[lexeme.len() - 1..], "\"");
-                let unescaped = &lexeme[1..lexeme.len() - 1];
-
-                StringLit(string_escapes::resolve_escape_seqs(unescaped))
-            }
-            l => l.clone(),
-        }),
-        t => t.clone(),
+    let val = match kind {
+        TokenKind::Identifier => Token::Identifier(unicode::translate(lexeme)),
+        TokenKind::Keyword(k) => Token::Keyword(k),
+        TokenKind::Separator(s) => Token::Separator(s),
+        TokenKind::Operator(o) => Token::Operator(o),
+        TokenKind::IntLiteral => Token::Literal(Int(unicode::translate(lexeme).into_owned())),
+        TokenKind::HexIntLiteral => Token::Literal(HexInt(unicode::translate(lexeme).into_owned())),
+        TokenKind::OctalIntLiteral => {
+            Token::Literal(OctalInt(unicode::translate(lexeme).into_owned()))
+        }
+        TokenKind::LongLiteral => Token::Literal(Long(without_suffix(lexeme, &['l', 'L']))),
+        TokenKind::FloatLiteral => Token::Literal(Float(without_suffix(lexeme, &['f', 'F']))),
+        TokenKind::DoubleLiteral => Token::Literal(Double(without_suffix(lexeme, &['d', 'D']))),
+        TokenKind::BoolLiteral => Token::Literal(Bool(unicode::translate(lexeme) == "true")),
+        TokenKind::CharLiteral => Token::Literal(make_char_literal(start, lexeme)?),
+        TokenKind::StringLiteral => Token::Literal(StringLit(resolve_escape_seqs(start, lexeme)?)),
+        TokenKind::NullLiteral => Token::Literal(Null),
     };
 
-    TokenInfo { val, start, lexeme }
+    Ok(TokenInfo {
+        val,
+        start,
+        lexeme,
+        doc: None,
+    })
 }
 
-fn make_char_literal(lexeme: &str) -> Literal {
-    // Strip quotes.
-    debug_assert_eq!(&lexeme[..1], "'");
-    debug_assert_eq!(&lexeme[lexeme.len() - 1..], "'");
-    let unescaped = &lexeme[1..lexeme.len() - 1];
-
+fn make_char_literal<'a>(start: Position<'a>, lexeme: &'a str) -> Result<Literal, TokenError<'a>> {
     // We could make the error messages better here by making `string_escapes` resolution lazy.
     // That way as soon as there's more than one char we just stop, instead of trying to resolve
     // escapes later in the line (whose errors would probably just confuse the user).
-    let s = string_escapes::resolve_escape_seqs(unescaped);
+    let s = resolve_escape_seqs(start, lexeme)?;
     let mut chars = s.chars();
     let ch = match chars.next() {
-        None => panic!("Empty char literal; must have a char between the quotes"),
+        None => return Err(TokenError::EmptyCharLiteral { start }),
         Some(ch) => ch,
     };
-    match chars.next() {
-        None => (),
-        Some(extra) => {
-            panic!(
-                "Char literal too long. Expected closing quote `'` but found `{}`",
-                extra
-            )
-        }
-    };
+    if chars.next().is_some() {
+        return Err(TokenError::CharLiteralTooLong { start, lexeme });
+    }
 
-    Char(ch)
+    Ok(Char(ch))
+}
+
+/// Strip the quotes from a string or char literal, and resolve the escape sequences in its body.
+///
+/// `start` is the position of the literal's opening quote.
+fn resolve_escape_seqs<'a>(start: Position<'a>, lexeme: &str) -> Result<String, TokenError<'a>> {
+    // Unicode escapes are translated first, so in principle even the quotes could be escaped.
+    let chars: Vec<_> = unicode::chars(lexeme).collect();
+    debug_assert!(chars.len() >= 2);
+    debug_assert_eq!(chars[0].1, chars[chars.len() - 1].1);
+    let body = chars[1..chars.len() - 1].iter().copied();
+
+    string_escapes::resolve_escape_seqs(body).map_err(|(offset, error)| {
+        let col = start.col + offset;
+        let pos = Position { col, ..start };
+        TokenError::BadEscape { pos, error }
+    })
 }
 
 #[cfg(test)]
-impl<S: Eq + Hash> DFA<S> {
+impl<S: Eq + std::hash::Hash, L> DFA<S, L> {
     /// Test helper to assert that the dfa accepts and/or rejects the given ascii strings.
     ///
     /// This helps us test NFA to DFA conversion, among other things.
@@ -202,7 +396,7 @@ impl<S: Eq + Hash> DFA<S> {
         let mut state = &self.init;
         for sym in symbols {
             let key = (state, &sym);
-            state = match self.delta.get(&key as &dyn KeyPair<_, _>) {
+            state = match self.delta.get(&key as &dyn key_pair::KeyPair<_, _>) {
                 Some(next) => next,
                 // Implicit "dead" state.
                 None => return false,
@@ -217,7 +411,7 @@ mod tests {
     use super::*;
     use crate::tokenizer;
     use crate::tokenizer::token_types::Keyword::If;
-    use crate::tokenizer::tokens::Token::Keyword;
+    use crate::tokenizer::token_types::TokenKind::Keyword;
 
     /// Helper struct for specifying small DFAs in unit tests.
     struct DFABuilder<'a> {
@@ -227,7 +421,7 @@ mod tests {
     }
 
     impl<'a> DFABuilder<'a> {
-        fn build(self) -> DFA<&'a str> {
+        fn build(self) -> DFA<&'a str, AcceptedStateLabel> {
             let init = self.init;
             let accepted = self.accepted.into_iter().collect();
             let delta = self
@@ -245,12 +439,12 @@ mod tests {
     }
 
     /// This DFA recognizes the language {"a", "ba"}.
-    fn simple_dfa() -> DFA<&'static str> {
+    fn simple_dfa() -> DFA<&'static str, AcceptedStateLabel> {
         DFABuilder {
             init: "init",
             accepted: vec![(
                 "accept",
-                AcceptedStateLabel::TokenType { type_: Keyword(If) },
+                AcceptedStateLabel::TokenType { kind: Keyword(If) },
             )],
             delta: vec![
                 (("init", 'a'), "accept"),
@@ -273,44 +467,75 @@ mod tests {
         dfa._check(&accepted, &rejected);
     }
 
+    /// Compiling keeps the same states, and groups symbols that are treated the same.
+    #[test]
+    fn compile_simple_dfa() {
+        let dfa = simple_dfa().compile();
+        assert_eq!(3, dfa.num_states());
+
+        // "a", "b", and everything else.
+        assert_eq!(3, dfa.num_classes());
+
+        let a = Symbol::new(b'a');
+        let b = Symbol::new(b'b');
+        let accept = dfa.step(dfa.init, a).unwrap();
+        assert!(dfa.label(accept).is_some());
+        assert_eq!(Some(accept), dfa.step(dfa.step(dfa.init, b).unwrap(), a));
+        assert_eq!(None, dfa.step(accept, a));
+        assert_eq!(None, dfa.step(dfa.init, Symbol::new(b'c')));
+    }
+
     /// Run the DFA on one line of ASCII text, to tokenize it.
-    fn tokenize_one_line<'a>(dfa: &'a DFA<&'a str>, line: &'a str) -> Vec<TokenOrComment<'a>> {
-        let positions = tokenizer::all_positions(iter::once(line));
+    fn tokenize_one_line<'a>(
+        dfa: &'a CompiledDFA<AcceptedStateLabel>,
+        comments: &'a CommentStates,
+        line: &'a str,
+    ) -> Vec<Result<Match<'a, AcceptedStateLabel>, TokenError<'a>>> {
+        let positions = tokenizer::Input::positions(iter::once(line));
 
         // Skip the special "newline" position at the end of `all_positions`.
         let positions = positions.take(line.len());
 
-        dfa.tokenize(positions).collect()
+        dfa.tokenize(positions, comments).collect()
     }
 
     /// Tokenize a short string of a's and b's.
     #[test]
     #[allow(non_snake_case)]
     fn tokenize_As_and_Bs() {
-        let dfa = simple_dfa();
+        let dfa = simple_dfa().compile();
+        let comments = dfa.comment_states(|_| false);
 
         let input = "abaaababa";
         let expected = vec!["a", "ba", "a", "a", "ba", "ba"];
 
         let mut actual = vec![];
-        for elem in tokenize_one_line(&dfa, input) {
-            match elem {
-                TokenOrComment::Token(t) => actual.push(t.lexeme),
-                _ => panic!(),
-            };
+        for elem in tokenize_one_line(&dfa, &comments, input) {
+            let m = elem.unwrap();
+            actual.push(&input[m.start.col..=m.end_inclusive.col]);
         }
         assert_eq!(expected, actual);
     }
 
     /// Fail to tokenize a short string of a's and b's.
     #[test]
-    #[should_panic]
     fn simple_tokenize_failure() {
-        let dfa = simple_dfa();
-
-        let input = "abaaababab";
-
-        // todo this test will fail once we implement robust error handling; fix it then.
-        tokenize_one_line(&dfa, input);
+        let dfa = simple_dfa().compile();
+        let comments = dfa.comment_states(|_| false);
+
+        let input = "abaabbab";
+        let output = tokenize_one_line(&dfa, &comments, input);
+
+        // The tokens before the error are reported. There are no delimiters after the error, so
+        // the rest of the input is skipped.
+        assert_eq!(output.len(), 4);
+        assert!(output[..3].iter().all(Result::is_ok));
+        match &output[3] {
+            Err(TokenError::NotAToken { start, end }) => {
+                assert_eq!(start.col, 4);
+                assert_eq!(end.col, 5);
+            }
+            other => panic!("{:?}", other),
+        }
     }
 }
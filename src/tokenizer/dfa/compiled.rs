@@ -0,0 +1,203 @@
+//! A compact, table-driven form of a DFA, which is what actually runs during tokenizing.
+//!
+//! States are renumbered to dense indices, and symbols are grouped into equivalence classes: two
+//! symbols are in the same class if every state treats them the same. (E.g., most letters behave
+//! identically, except for the ones that start keywords.) The transition table then only needs one
+//! column per class, instead of one per symbol.
+
+use super::key_pair::KeyPair;
+use crate::tokenizer::dfa::DFA;
+use crate::tokenizer::states::Symbol;
+use std::collections::{HashMap as Map, VecDeque};
+use std::hash::Hash;
+
+/// Marks a transition to the implicit "dead" state in `CompiledDFA::delta`.
+pub(super) const DEAD: u32 = u32::MAX;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct CompiledDFA<L> {
+    pub(super) init: u32,
+    /// The equivalence class of each symbol, indexed by `Symbol::index`.
+    pub(super) classes: [u8; Symbol::COUNT],
+    pub(super) num_classes: usize,
+    /// The transition table, indexed by `state * num_classes + class`. Contains `DEAD` if there's
+    /// no transition.
+    pub(super) delta: Vec<u32>,
+    /// The label of each state, if it's accepted.
+    pub(super) accepted: Vec<Option<L>>,
+}
+
+impl<L> CompiledDFA<L> {
+    /// The state after reading `sym` from `state`, or `None` if that's the "dead" state.
+    #[inline]
+    pub fn step(&self, state: u32, sym: Symbol) -> Option<u32> {
+        let class = self.classes[sym.index()] as usize;
+        let next = self.delta[state as usize * self.num_classes + class];
+        if next == DEAD {
+            None
+        } else {
+            Some(next)
+        }
+    }
+
+    #[inline]
+    pub fn label(&self, state: u32) -> Option<&L> {
+        self.accepted[state as usize].as_ref()
+    }
+
+    /// Max munch: the longest prefix of `input` that the DFA accepts, as its length in symbols,
+    /// along with its label. Like `NFA::_longest_match`, but fast.
+    pub fn _longest_match(&self, input: impl IntoIterator<Item = Symbol>) -> Option<(usize, &L)> {
+        let mut longest = None;
+        let mut state = self.init;
+        for (i, sym) in input.into_iter().enumerate() {
+            state = match self.step(state, sym) {
+                Some(next) => next,
+                None => break,
+            };
+            if let Some(label) = self.label(state) {
+                longest = Some((i + 1, label));
+            }
+        }
+        longest
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.accepted.len()
+    }
+
+    pub fn num_classes(&self) -> usize {
+        self.num_classes
+    }
+
+    /// The number of (state, symbol) pairs with a transition to a live state.
+    pub fn num_transitions(&self) -> usize {
+        let mut per_class = vec![0; self.num_classes];
+        for &class in &self.classes {
+            per_class[class as usize] += 1;
+        }
+
+        let row_transitions = |row: &[u32]| -> usize {
+            row.iter()
+                .zip(&per_class)
+                .filter(|&(&next, _)| next != DEAD)
+                .map(|(_, n)| n)
+                .sum()
+        };
+        self.delta
+            .chunks(self.num_classes)
+            .map(row_transitions)
+            .sum()
+    }
+
+    /// For each state, can it reach an accepting state whose label satisfies `is_target`?
+    pub fn can_reach(&self, is_target: impl Fn(&L) -> bool) -> Vec<bool> {
+        let num_states = self.num_states();
+
+        // Search backwards from the targets.
+        let mut preds = vec![vec![]; num_states];
+        for (state, row) in self.delta.chunks(self.num_classes).enumerate() {
+            for &next in row {
+                if next != DEAD {
+                    preds[next as usize].push(state);
+                }
+            }
+        }
+
+        let mut reachable: Vec<bool> = self
+            .accepted
+            .iter()
+            .map(|label| label.as_ref().is_some_and(&is_target))
+            .collect();
+        let mut stack: Vec<usize> = (0..num_states).filter(|&s| reachable[s]).collect();
+        while let Some(state) = stack.pop() {
+            for &pred in &preds[state] {
+                if !reachable[pred] {
+                    reachable[pred] = true;
+                    stack.push(pred);
+                }
+            }
+        }
+        reachable
+    }
+}
+
+impl<S: Eq + Hash, L: Clone> DFA<S, L> {
+    /// Compile the DFA into a dense transition table.
+    ///
+    /// States are numbered in BFS order from `init`, so for a DFA from `minimize`, the numbering
+    /// doesn't change. Unreachable states are dropped.
+    pub fn compile(&self) -> CompiledDFA<L> {
+        if self.accepted.contains_key(&self.init) {
+            panic!("Empty matches unsupported; please fix your DFA");
+        }
+
+        // Number the states.
+        let mut numbering: Map<&S, u32> = Map::new();
+        let mut order = vec![];
+        numbering.insert(&self.init, 0);
+        let mut queue = VecDeque::new();
+        queue.push_back(&self.init);
+        while let Some(s) = queue.pop_front() {
+            order.push(s);
+            for sym in Symbol::all() {
+                if let Some(next) = self.delta.get(&(s, &sym) as &dyn KeyPair<_, _>) {
+                    if !numbering.contains_key(next) {
+                        numbering.insert(next, numbering.len() as u32);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        // The column of the full (uncompressed) table for each symbol.
+        let columns: Vec<Vec<u32>> = Symbol::all()
+            .map(|sym| {
+                order
+                    .iter()
+                    .map(
+                        |&s| match self.delta.get(&(s, &sym) as &dyn KeyPair<_, _>) {
+                            Some(next) => numbering[next],
+                            None => DEAD,
+                        },
+                    )
+                    .collect()
+            })
+            .collect();
+
+        // Symbols with identical columns share a class.
+        let mut class_of_column: Map<&[u32], u8> = Map::new();
+        let mut classes = [0; Symbol::COUNT];
+        let mut class_columns = vec![];
+        for (i, column) in columns.iter().enumerate() {
+            let next = class_of_column.len() as u8;
+            classes[i] = *class_of_column.entry(column).or_insert_with(|| {
+                class_columns.push(column);
+                next
+            });
+        }
+        let num_classes = class_columns.len();
+
+        // Lay out the table row by row.
+        let mut delta = vec![DEAD; order.len() * num_classes];
+        for (class, column) in class_columns.iter().enumerate() {
+            for (state, &next) in column.iter().enumerate() {
+                delta[state * num_classes + class] = next;
+            }
+        }
+
+        let accepted = order
+            .iter()
+            .map(|&s| self.accepted.get(s).cloned())
+            .collect();
+
+        CompiledDFA {
+            init: 0,
+            classes,
+            num_classes,
+            delta,
+            accepted,
+        }
+    }
+}
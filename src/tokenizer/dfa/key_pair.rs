@@ -50,7 +50,7 @@ impl<'a, A: 'a, B: 'a> Borrow<dyn KeyPair<A, B> + 'a> for (A, B) {
     }
 }
 
-impl<'a, A: Hash, B: Hash> Hash for (dyn KeyPair<A, B> + 'a) {
+impl<'a, A: Hash, B: Hash> Hash for dyn KeyPair<A, B> + 'a {
     /// Crucially, this is the same way a tuple (A, B) is hashed. The implementation of std HashMap
     /// relies on this; see the documentation for `HashMap::get`.
     fn hash<H: Hasher>(&self, state: &mut H) {
@@ -59,10 +59,10 @@ impl<'a, A: Hash, B: Hash> Hash for (dyn KeyPair<A, B> + 'a) {
     }
 }
 
-impl<'a, A: PartialEq, B: PartialEq> PartialEq for (dyn KeyPair<A, B> + 'a) {
+impl<'a, A: PartialEq, B: PartialEq> PartialEq for dyn KeyPair<A, B> + 'a {
     fn eq(&self, other: &dyn KeyPair<A, B>) -> bool {
         self.a() == other.a() && self.b() == other.b()
     }
 }
 
-impl<'a, A: Eq, B: Eq> Eq for (dyn KeyPair<A, B> + 'a) {}
+impl<'a, A: Eq, B: Eq> Eq for dyn KeyPair<A, B> + 'a {}
@@ -1,20 +1,27 @@
+use crate::tokenizer::tokens::EscapeError;
 use std::iter::Peekable;
 
 /// Resolve the Java escape sequences in a string.
-pub fn resolve_escape_seqs(literal: &str) -> String {
-    let mut input = literal.chars().peekable();
-    let mut buf = String::with_capacity(literal.len());
+///
+/// The input characters are paired with their byte offsets, as in `str::char_indices`. On failure,
+/// returns the offset of the offending backslash, together with the reason the escape sequence is
+/// invalid.
+pub fn resolve_escape_seqs(
+    literal: impl Iterator<Item = (usize, char)>,
+) -> Result<String, (usize, EscapeError)> {
+    let mut input = literal.peekable();
+    let mut buf = String::new();
 
-    while let Some(c) = input.next() {
+    while let Some((i, c)) = input.next() {
         if c != '\\' {
             buf.push(c);
         } else {
-            let resolved = resolve_once(&mut input);
+            let resolved = resolve_once(&mut input).map_err(|e| (i, e))?;
             buf.push(resolved);
         }
     }
 
-    buf
+    Ok(buf)
 }
 
 /// Resolve a single escape sequence.
@@ -23,66 +30,64 @@ pub fn resolve_escape_seqs(literal: &str) -> String {
 ///
 /// This consumes up to and including the end of the escape sequence, if successful. No guarantees
 /// about what is consumed on failure.
-fn resolve_once(input: &mut Peekable<impl Iterator<Item = char>>) -> char {
+fn resolve_once(
+    input: &mut Peekable<impl Iterator<Item = (usize, char)>>,
+) -> Result<char, EscapeError> {
     match input.next() {
         // Technically this case should never happen, because the Java lang NFA only matches string
         // literals that *don't* have a backslash before the closing quote.
-        None => panic!("Empty escape sequence: backslash at end of string literal"), // todo
+        None => Err(EscapeError::Empty),
 
-        Some(next_char) => match next_char {
-            'b' => '\x08', // '\b': backspace
-            't' => '\t',
-            'n' => '\n',
-            'f' => '\x0c', // '\f': form feed
-            'r' => '\r',
-            '"' => '"',
-            '\'' => '\'',
-            '\\' => '\\',
+        Some((_, next_char)) => match next_char {
+            'b' => Ok('\x08'), // '\b': backspace
+            't' => Ok('\t'),
+            'n' => Ok('\n'),
+            'f' => Ok('\x0c'), // '\f': form feed
+            'r' => Ok('\r'),
+            '"' => Ok('"'),
+            '\'' => Ok('\''),
+            '\\' => Ok('\\'),
             '0'..='7' => {
                 // Consume up to a total of 4 digits.
-                let mut digits = Vec::with_capacity(4);
+                let mut digits = String::with_capacity(4);
                 digits.push(next_char);
                 while digits.len() < 4 {
                     match input.peek() {
-                        Some('0'..='7') => {
-                            digits.push(input.next().unwrap());
+                        Some((_, '0'..='7')) => {
+                            digits.push(input.next().unwrap().1);
                         }
                         _ => break,
                     }
                 }
-                debug_assert!(1 <= digits.len() && digits.len() <= 4);
+                debug_assert!(!digits.is_empty() && digits.len() <= 4);
 
-                if digits.len() == 4 && digits[0] >= '4' {
-                    panic!(
-                        "Octal literal too large (largest is \\3777), found \\{}{}{}{}",
-                        digits[0], digits[1], digits[2], digits[3]
-                    ); // todo
+                if digits.len() == 4 && digits.as_bytes()[0] >= b'4' {
+                    return Err(EscapeError::OctalTooLarge(digits));
                 }
 
                 octal_to_utf8(&digits)
             }
-            '8' | '9' => panic!(
-                "Numeric escape sequences are octal (largest digit is 7), found \\{}", // todo
-                next_char
-            ),
-            _ => panic!("Invalid or unknown escape sequence: \\{}", next_char), // todo
+            '8' | '9' => Err(EscapeError::NotOctal(next_char)),
+            _ => Err(EscapeError::Unknown(next_char)),
         },
     }
 }
 
 /// Turn a sequence of octal digits into a unicode scalar value.
-fn octal_to_utf8(digits: &[char]) -> char {
+fn octal_to_utf8(digits: &str) -> Result<char, EscapeError> {
     let mut ascii_val = 0u32;
-    for &c in digits {
-        debug_assert!('0' <= c && c <= '7');
+    for c in digits.chars() {
+        debug_assert!(('0'..='7').contains(&c));
         let digit = c as u32 - '0' as u32;
         ascii_val <<= 3;
         ascii_val += digit;
     }
     debug_assert!(ascii_val <= 0o3777);
 
-    assert!(ascii_val < 128); // todo error handling
-    ascii_val as u8 as char
+    if ascii_val >= 128 {
+        return Err(EscapeError::NotAscii(digits.to_string()));
+    }
+    Ok(ascii_val as u8 as char)
 }
 
 #[cfg(test)]
@@ -91,7 +96,24 @@ mod tests {
 
     #[test]
     fn example() {
-        let s = resolve_escape_seqs(r"asdf\\asdf\'");
-        assert_eq!(s, r"asdf\asdf'");
+        let s = resolve_escape_seqs(r"asdf\\asdf\'".char_indices());
+        assert_eq!(s, Ok(String::from(r"asdf\asdf'")));
+    }
+
+    #[test]
+    fn bad_escapes() {
+        for (input, expected) in [
+            (r"ab\q", (2, EscapeError::Unknown('q'))),
+            (r"\9", (0, EscapeError::NotOctal('9'))),
+            (
+                r"xy\4000",
+                (2, EscapeError::OctalTooLarge(String::from("4000"))),
+            ),
+            (r"\377", (0, EscapeError::NotAscii(String::from("377")))),
+            ("\\", (0, EscapeError::Empty)),
+        ] {
+            let actual = resolve_escape_seqs(input.char_indices());
+            assert_eq!(actual, Err(expected), "{}", input);
+        }
     }
 }
@@ -0,0 +1,173 @@
+//! A plain-text format for `CompiledDFA`s, so that a DFA can be built once, checked in, and loaded
+//! cheaply at runtime.
+//!
+//! For example:
+//! ```text
+//! classes 0 0 1 2 ...
+//! row 1 - 2
+//! row - 3 -
+//! accept if
+//! ```
+//! - `classes` gives the class of each symbol, in order of `Symbol::index`.
+//! - Each `row` lists the transitions out of a state, one per class. `-` means the "dead" state.
+//!   States are numbered in order, starting from the initial state, 0.
+//! - An `accept` line labels the state of the row above it. How labels are written is up to the
+//!   caller.
+//!
+//! Blank lines, and lines starting with `#`, are ignored.
+
+use super::compiled::{CompiledDFA, DEAD};
+use crate::tokenizer::states::Symbol;
+use std::convert::TryInto;
+use std::fmt::Write;
+
+impl<L> CompiledDFA<L> {
+    /// Write the DFA in the table format. `write_label` must not produce any newlines.
+    pub fn to_table(&self, write_label: impl Fn(&L) -> String) -> String {
+        let mut out = String::new();
+
+        let classes: Vec<_> = self.classes.iter().map(u8::to_string).collect();
+        writeln!(out, "classes {}", classes.join(" ")).unwrap();
+
+        for (state, row) in self.delta.chunks(self.num_classes).enumerate() {
+            let row: Vec<_> = row
+                .iter()
+                .map(|&next| match next {
+                    DEAD => String::from("-"),
+                    next => next.to_string(),
+                })
+                .collect();
+            writeln!(out, "row {}", row.join(" ")).unwrap();
+
+            if let Some(label) = &self.accepted[state] {
+                writeln!(out, "accept {}", write_label(label)).unwrap();
+            }
+        }
+
+        out
+    }
+
+    /// Read a DFA in the table format. `read_label` returns `None` if a label is invalid.
+    pub fn from_table(table: &str, read_label: impl Fn(&str) -> Option<L>) -> Result<Self, String> {
+        let mut classes = None;
+        let mut delta = vec![];
+        let mut accepted = vec![];
+        let mut num_classes = 0;
+
+        for (i, line) in table.lines().enumerate() {
+            let err = |msg: &str| format!("line {}: {}", i + 1, msg);
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+            match kind {
+                "classes" => {
+                    let parsed: Vec<u8> = rest
+                        .split(' ')
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| err("bad class"))?;
+                    let parsed: [u8; Symbol::COUNT] = parsed
+                        .try_into()
+                        .map_err(|_| err("wrong number of classes"))?;
+                    num_classes = *parsed.iter().max().unwrap() as usize + 1;
+                    classes = Some(parsed);
+                }
+                "row" => {
+                    let row = rest
+                        .split(' ')
+                        .map(|next| match next {
+                            "-" => Ok(DEAD),
+                            next => next.parse::<u32>(),
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| err("bad transition"))?;
+                    if row.len() != num_classes {
+                        return Err(err("wrong number of transitions"));
+                    }
+                    delta.extend(row);
+                    accepted.push(None);
+                }
+                "accept" => {
+                    let label = read_label(rest).ok_or_else(|| err("bad label"))?;
+                    match accepted.last_mut() {
+                        Some(l @ None) => *l = Some(label),
+                        _ => return Err(err("accept must follow a row")),
+                    }
+                }
+                _ => return Err(err("unknown line")),
+            }
+        }
+
+        let classes = classes.ok_or("missing classes")?;
+        let num_states = accepted.len();
+        if num_states == 0 {
+            return Err(String::from("no states"));
+        }
+        if delta
+            .iter()
+            .any(|&next| next != DEAD && next as usize >= num_states)
+        {
+            return Err(String::from("transition to a nonexistent state"));
+        }
+
+        Ok(CompiledDFA {
+            init: 0,
+            classes,
+            num_classes,
+            delta,
+            accepted,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let table = "\
+# A comment.
+classes 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 1 2 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+row - 1 2
+row - - -
+accept operator ||
+row - 1 -
+accept char 32
+";
+        let dfa = CompiledDFA::from_table(table, |s| Some(String::from(s))).unwrap();
+        assert_eq!(3, dfa.num_states());
+        assert_eq!(3, dfa.num_classes());
+        assert_eq!(3, dfa.num_transitions());
+        assert_eq!(Some("operator ||"), dfa.label(1).map(String::as_str));
+        assert_eq!(Some("char 32"), dfa.label(2).map(String::as_str));
+
+        // Comments aren't preserved.
+        assert_eq!(
+            table.split_once('\n').unwrap().1,
+            dfa.to_table(String::clone)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for (table, expected) in [
+            ("", "missing classes"),
+            ("classes 0 1", "line 1: wrong number of classes"),
+            ("row 1", "line 1: wrong number of transitions"),
+            ("rows", "line 1: unknown line"),
+            ("accept xy", "line 1: accept must follow a row"),
+            ("accept x", "line 1: bad label"),
+        ] {
+            // Only labels of two or more chars are valid.
+            let read_label = |s: &str| Some(s.len()).filter(|&n| n > 1);
+            assert_eq!(
+                Err(String::from(expected)),
+                CompiledDFA::from_table(table, read_label)
+            );
+        }
+    }
+}
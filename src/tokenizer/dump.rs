@@ -0,0 +1,404 @@
+//! Dumps of NFAs and DFAs for debugging, as Graphviz DOT or as plain text.
+//!
+//! Priority bugs (e.g. a keyword lexed as an identifier) are easiest to track down by looking at the
+//! automaton. The full Joos automaton has hundreds of states, so you can also dump just the part
+//! that's reachable after reading some prefix:
+//! ```
+//! # use cs444::tokenizer::Tokenizer;
+//! let spec = Tokenizer::new().lexer().spec().clone();
+//! let dump = spec.dump_dfa("whil").unwrap();
+//!
+//! // Render with e.g. `dot -Tsvg`.
+//! assert!(dump.to_dot().starts_with("digraph {"));
+//! // Or just print it.
+//! assert!(dump.to_string().contains("accept while"));
+//! ```
+//!
+//! States are renumbered in BFS order, and transitions between the same two states are merged into
+//! a char class like `[a-zA-Z_$]`.
+
+use crate::tokenizer::dfa::key_pair::KeyPair;
+use crate::tokenizer::dfa::DFA;
+use crate::tokenizer::nfa::NFA;
+use crate::tokenizer::states::Symbol;
+use std::collections::{HashMap as Map, HashSet as Set, VecDeque};
+use std::fmt::{self, Write};
+use std::hash::Hash;
+
+/// Part of an automaton, ready to print. Use `to_dot` for Graphviz, or `Display` for plain text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dump {
+    /// States are numbered by their index. The first `num_roots` states are where the dump starts.
+    states: Vec<DumpState>,
+    num_roots: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DumpState {
+    /// The label, if the state is accepted.
+    label: Option<String>,
+    edges: Vec<Edge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Edge {
+    /// E.g. `[a-z]`. `None` for an epsilon transition.
+    symbols: Option<String>,
+    target: usize,
+}
+
+impl Dump {
+    /// Explore everything reachable from `roots`.
+    ///
+    /// `successors` lists the transitions out of a state, with `None` for epsilon. Symbols should
+    /// be in order of `Symbol::index`.
+    fn new<S: Clone + Eq + Hash>(
+        roots: Vec<S>,
+        successors: impl Fn(&S) -> Vec<(Option<Symbol>, S)>,
+        label: impl Fn(&S) -> Option<String>,
+    ) -> Self {
+        let mut numbering: Map<S, usize> = Map::new();
+        let mut queue = VecDeque::new();
+        for root in roots {
+            if !numbering.contains_key(&root) {
+                numbering.insert(root.clone(), numbering.len());
+                queue.push_back(root);
+            }
+        }
+        let num_roots = numbering.len();
+
+        let mut states = vec![];
+        while let Some(s) = queue.pop_front() {
+            let mut edges: Vec<Edge> = vec![];
+            // Symbols on the way to each target, in order of first appearance.
+            let mut symbols: Vec<(usize, Vec<Symbol>)> = vec![];
+
+            for (sym, next) in successors(&s) {
+                let target = match numbering.get(&next) {
+                    Some(&n) => n,
+                    None => {
+                        let n = numbering.len();
+                        numbering.insert(next.clone(), n);
+                        queue.push_back(next);
+                        n
+                    }
+                };
+                match sym {
+                    None => edges.push(Edge {
+                        symbols: None,
+                        target,
+                    }),
+                    Some(sym) => match symbols.iter_mut().find(|(t, _)| *t == target) {
+                        Some((_, syms)) => syms.push(sym),
+                        None => symbols.push((target, vec![sym])),
+                    },
+                }
+            }
+            edges.extend(symbols.into_iter().map(|(target, syms)| Edge {
+                symbols: Some(char_class(&syms)),
+                target,
+            }));
+
+            states.push(DumpState {
+                label: label(&s),
+                edges,
+            });
+        }
+
+        Self { states, num_roots }
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Render as a Graphviz digraph.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n    rankdir=LR;\n    node [shape=circle];\n");
+        for root in 0..self.num_roots {
+            writeln!(out, "    start{} [shape=point];", root).unwrap();
+            writeln!(out, "    start{} -> {};", root, root).unwrap();
+        }
+
+        for (i, state) in self.states.iter().enumerate() {
+            match &state.label {
+                Some(label) => writeln!(
+                    out,
+                    "    {} [shape=doublecircle, label=\"{}\\n{}\"];",
+                    i,
+                    i,
+                    dot_escape(label)
+                ),
+                None => writeln!(out, "    {};", i),
+            }
+            .unwrap();
+
+            for edge in &state.edges {
+                match &edge.symbols {
+                    Some(symbols) => writeln!(
+                        out,
+                        "    {} -> {} [label=\"{}\"];",
+                        i,
+                        edge.target,
+                        dot_escape(symbols)
+                    ),
+                    None => writeln!(
+                        out,
+                        "    {} -> {} [label=\"ε\", style=dashed];",
+                        i, edge.target
+                    ),
+                }
+                .unwrap();
+            }
+        }
+
+        out.push_str("}\n");
+        out
+    }
+}
+
+impl fmt::Display for Dump {
+    /// One line per state, followed by an indented line per transition.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, state) in self.states.iter().enumerate() {
+            write!(f, "{}", i)?;
+            if i < self.num_roots {
+                write!(f, " (start)")?;
+            }
+            if let Some(label) = &state.label {
+                write!(f, " accept {}", label)?;
+            }
+            writeln!(f)?;
+
+            for edge in &state.edges {
+                let symbols = edge.symbols.as_deref().unwrap_or("ε");
+                writeln!(f, "    {} -> {}", symbols, edge.target)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: Copy + Ord + Hash, L> NFA<S, L> {
+    /// Dump the states reachable after reading `prefix`, or `None` if there aren't any. Use an
+    /// empty prefix to dump the whole NFA.
+    pub fn dump(&self, prefix: &str, write_label: impl Fn(&L) -> String) -> Option<Dump> {
+        let mut current = self.epsilon_closure(vec![self.init]);
+        for c in prefix.chars() {
+            let sym = Symbol::from_char(c);
+            let next = current
+                .iter()
+                .filter_map(|&s| self.delta.get(&(s, sym)))
+                .flatten()
+                .copied()
+                .collect();
+            current = self.epsilon_closure(next);
+        }
+        if current.is_empty() {
+            return None;
+        }
+
+        let successors = |&s: &S| {
+            let epsilon = self.epsilon.get(&s).into_iter().flatten();
+            let delta = Symbol::all().flat_map(|sym| {
+                let targets = self.delta.get(&(s, sym)).into_iter().flatten();
+                targets.map(move |&t| (Some(sym), t))
+            });
+            epsilon.map(|&t| (None, t)).chain(delta).collect()
+        };
+        let label = |s: &S| self.accepted.get(s).map(&write_label);
+        Some(Dump::new(current, successors, label))
+    }
+
+    /// All the states reachable from `states` by epsilon transitions, sorted.
+    fn epsilon_closure(&self, mut states: Vec<S>) -> Vec<S> {
+        let mut seen: Set<S> = states.iter().copied().collect();
+        let mut stack = states.clone();
+        while let Some(s) = stack.pop() {
+            for &next in self.epsilon.get(&s).into_iter().flatten() {
+                if seen.insert(next) {
+                    states.push(next);
+                    stack.push(next);
+                }
+            }
+        }
+        states.sort_unstable();
+        states.dedup();
+        states
+    }
+}
+
+impl<S: Clone + Eq + Hash, L> DFA<S, L> {
+    /// Dump the states reachable after reading `prefix`, or `None` if it leads to the "dead"
+    /// state. Use an empty prefix to dump the whole DFA.
+    pub fn dump(&self, prefix: &str, write_label: impl Fn(&L) -> String) -> Option<Dump> {
+        let mut state = &self.init;
+        for c in prefix.chars() {
+            let sym = Symbol::from_char(c);
+            state = self.delta.get(&(state, &sym) as &dyn KeyPair<_, _>)?;
+        }
+
+        let successors = |s: &S| {
+            Symbol::all()
+                .filter_map(|sym| {
+                    let next = self.delta.get(&(s, &sym) as &dyn KeyPair<_, _>)?;
+                    Some((Some(sym), next.clone()))
+                })
+                .collect()
+        };
+        let label = |s: &S| self.accepted.get(s).map(&write_label);
+        Some(Dump::new(vec![state.clone()], successors, label))
+    }
+}
+
+/// Describe a set of symbols, sorted by `Symbol::index`, in the syntax of `regex`. E.g. `a`, or
+/// `[a-zA-Z_$]`, or `[^\n]`.
+fn char_class(symbols: &[Symbol]) -> String {
+    if let [sym] = symbols {
+        return escape(*sym, false);
+    }
+
+    // Big sets are easier to read as a negated class.
+    let (negated, members): (_, Vec<_>) = if symbols.len() > Symbol::COUNT / 2 {
+        let complement = Symbol::all().filter(|s| !symbols.contains(s)).collect();
+        (true, complement)
+    } else {
+        (false, symbols.to_vec())
+    };
+
+    let mut out = String::from(if negated { "[^" } else { "[" });
+    let mut i = 0;
+    while i < members.len() {
+        // Find the end of this run of consecutive symbols.
+        let mut j = i;
+        while j + 1 < members.len() && members[j + 1].index() == members[j].index() + 1 {
+            j += 1;
+        }
+
+        out.push_str(&escape(members[i], true));
+        if j >= i + 2 {
+            out.push('-');
+        }
+        if j > i {
+            out.push_str(&escape(members[j], true));
+        }
+        i = j + 1;
+    }
+    out.push(']');
+    out
+}
+
+/// Write a symbol so that it can be read back by `regex`. `NON_ASCII` doesn't have a syntax, so it's
+/// written as `<non-ASCII>`.
+fn escape(sym: Symbol, in_class: bool) -> String {
+    let special: &[char] = if in_class {
+        &['\\', ']', '^', '-']
+    } else {
+        &['\\', '|', '*', '+', '?', '(', ')', '[', ']', '.']
+    };
+    match sym.to_char() {
+        _ if sym == Symbol::NON_ASCII => String::from("<non-ASCII>"),
+        '\n' => String::from("\\n"),
+        '\t' => String::from("\\t"),
+        '\r' => String::from("\\r"),
+        '\x0C' => String::from("\\f"),
+        c if special.contains(&c) => format!("\\{}", c),
+        c if c.is_ascii_graphic() || c == ' ' => c.to_string(),
+        c => format!("\\x{:02x}", c as u32),
+    }
+}
+
+/// Escape a string for a quoted DOT label.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syms(s: &str) -> Vec<Symbol> {
+        s.chars().map(Symbol::from_char).collect()
+    }
+
+    #[test]
+    fn char_classes() {
+        for (symbols, expected) in [
+            (syms("a"), "a"),
+            (syms("*"), "\\*"),
+            (syms("ab"), "[ab]"),
+            (syms("$0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ_"), "[$0-9A-Z_]"),
+            (syms("-]^"), "[\\-\\]\\^]"),
+        ] {
+            assert_eq!(expected, char_class(&symbols));
+        }
+
+        let all_but_newline: Vec<_> = Symbol::all().filter(|s| s.to_char() != '\n').collect();
+        assert_eq!("[^\\n]", char_class(&all_but_newline));
+        let ascii: Vec<_> = Symbol::all().filter(|&s| s != Symbol::NON_ASCII).collect();
+        assert_eq!("[^<non-ASCII>]", char_class(&ascii));
+    }
+
+    /// Recognizes "ab" and "ac", with an epsilon transition.
+    fn simple_nfa() -> NFA<u32, &'static str> {
+        NFA {
+            init: 0,
+            accepted: vec![(3, "AB"), (4, "AC")].into_iter().collect(),
+            delta: vec![
+                ((0, Symbol::new(b'a')), vec![1]),
+                ((2, Symbol::new(b'b')), vec![3]),
+                ((2, Symbol::new(b'c')), vec![4]),
+            ]
+            .into_iter()
+            .collect(),
+            epsilon: vec![(1, vec![2])].into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn dump_nfa() {
+        let nfa = simple_nfa();
+        let dump = nfa.dump("", |l| l.to_string()).unwrap();
+        let expected = "\
+0 (start)
+    a -> 1
+1
+    ε -> 2
+2
+    b -> 3
+    c -> 4
+3 accept AB
+4 accept AC
+";
+        assert_eq!(expected, dump.to_string());
+
+        // After the prefix, we could be in either state 1 or 2.
+        let dump = nfa.dump("a", |l| l.to_string()).unwrap();
+        assert_eq!(4, dump.num_states());
+        assert!(dump
+            .to_string()
+            .starts_with("0 (start)\n    ε -> 1\n1 (start)\n"));
+
+        assert_eq!(None, nfa.dump("b", |l| l.to_string()));
+    }
+
+    #[test]
+    fn dump_dfa() {
+        let dfa = simple_nfa().to_dfa().minimize();
+        let dump = dfa.dump("", |l| l.to_string()).unwrap();
+        assert_eq!(4, dump.num_states());
+
+        let dot = dump.to_dot();
+        assert!(dot.contains("    start0 -> 0;\n"), "{}", dot);
+        assert!(dot.contains("    0 -> 1 [label=\"a\"];\n"), "{}", dot);
+        assert!(
+            dot.contains("[shape=doublecircle, label=\"2\\nAB\"]"),
+            "{}",
+            dot
+        );
+
+        let dump = dfa.dump("ab", |l| l.to_string()).unwrap();
+        assert_eq!("0 (start) accept AB\n", dump.to_string());
+        assert_eq!(None, dfa.dump("abc", |l| l.to_string()));
+    }
+}
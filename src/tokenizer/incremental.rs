@@ -0,0 +1,404 @@
+//! Incremental re-tokenization, for editors: after an edit, only the part of the text that the edit
+//! could affect is scanned again.
+//!
+//! The scanner works in steps, each starting where the last one left off (see
+//! `CompiledDFA::scan`). A step's result only depends on the text from its start up to as far as
+//! the scanner looked (`Scanned::read_to`). So after an edit:
+//! - Steps that didn't look as far as the edit are kept as-is.
+//! - Scanning restarts at the first step that did.
+//! - Once a new step starts exactly where an old step after the edit started, the rest of the old
+//!   steps are kept too (shifted over), since they scanned exactly the same text.
+//!
+//! So e.g. typing `/*` re-scans up to the end of the new comment, or of the file if it's never
+//! closed.
+//!
+//! One wrinkle is unicode escapes: how a line is decoded can depend on the whole line (see
+//! `unicode::decode`). So an edit is treated as covering each line it touches, in full.
+
+use crate::source_map::{FileId, SourceFile};
+use crate::tokenizer::dfa::Scanned;
+use crate::tokenizer::lexer::{Action, Lexeme, RuleId};
+use crate::tokenizer::tokens::{TokenError, TokenInfo, TokenOrComment};
+use crate::tokenizer::{line_positions, unicode, Position, Tokenizer};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// The tokens of a text that's being edited. See `Tokenizer::tokenize_incremental`.
+///
+/// Spans of the tokens have `FileId::ANONYMOUS`.
+#[derive(Debug)]
+pub struct IncrementalTokens<'t> {
+    tokenizer: &'t Tokenizer,
+    file: SourceFile,
+    /// Covers the whole text, in order.
+    steps: Vec<Step>,
+}
+
+/// A `Scanned`, with byte offsets instead of positions, so that it can be kept across edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Step {
+    start: usize,
+    read_to: usize,
+    kind: StepKind,
+}
+
+/// The result of a step. The errors are the ones that come from scanning, not from `token_info`;
+/// those are found again each time the tokens are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepKind {
+    Match { rule: RuleId, end_inclusive: usize },
+    NotAToken { end: usize },
+    NonAsciiChar { pos: usize },
+    UnclosedComment { end: usize },
+    BadUnicodeEscape { pos: usize },
+}
+
+impl<'t> IncrementalTokens<'t> {
+    pub(super) fn new(tokenizer: &'t Tokenizer, src: String) -> Self {
+        let file = SourceFile::new(FileId::ANONYMOUS, String::new(), src);
+        let steps = tokenizer
+            .lexer
+            .scan(positions_from(&file, 0))
+            .map(Step::new)
+            .collect();
+        Self {
+            tokenizer,
+            file,
+            steps,
+        }
+    }
+
+    /// The current text.
+    pub fn src(&self) -> &str {
+        self.file.src()
+    }
+
+    /// Replace `range` of the text with `text`, and re-tokenize.
+    ///
+    /// Returns the range of the new text that was scanned again. Tokens outside it were reused.
+    pub fn edit(&mut self, range: Range<usize>, text: &str) -> Range<usize> {
+        let old = &self.file;
+        let old_src = old.src();
+        assert!(range.start <= range.end && range.end <= old_src.len());
+
+        // Round the edit out to whole lines. There's no end to it if it touches the last line.
+        let start = old.line_start(old.position(range.start).line_num);
+        let end_line = old.position(range.end).line_num + 1;
+        let end = (end_line < old.num_lines()).then(|| old.line_start(end_line));
+        // Where the old offsets from `end` onwards end up.
+        let shift = |offset: usize| offset - range.end + range.start + text.len();
+
+        let new_src = [&old_src[..range.start], text, &old_src[range.end..]].concat();
+        let file = SourceFile::new(FileId::ANONYMOUS, String::new(), new_src);
+
+        // Restart at the first step that looked at the edit.
+        let first = self
+            .steps
+            .iter()
+            .position(|s| s.read_to > start)
+            .unwrap_or(self.steps.len());
+        let restart = self.steps.get(first).map_or(start, |s| s.start);
+
+        let mut new_steps = vec![];
+        let mut old_steps = first..self.steps.len();
+        let mut synced = None;
+        for scanned in self.tokenizer.lexer.scan(positions_from(&file, restart)) {
+            let step = Step::new(scanned);
+
+            // Is this where an old step after the edit started?
+            if let Some(end) = end.filter(|&end| step.start >= shift(end)) {
+                let old_start = step.start - shift(end) + end;
+                let mut rest = old_steps
+                    .by_ref()
+                    .skip_while(|&i| self.steps[i].start < old_start);
+                if let Some(i) = rest.next() {
+                    if self.steps[i].start == old_start {
+                        synced = Some(i);
+                        break;
+                    }
+                    old_steps = i..self.steps.len();
+                }
+            }
+            new_steps.push(step);
+        }
+
+        let rescanned = restart..synced.map_or(file.src().len(), |i| shift(self.steps[i].start));
+        let reused: Vec<Step> = match synced {
+            Some(i) => self.steps[i..].iter().map(|s| s.shifted(shift)).collect(),
+            None => vec![],
+        };
+        self.steps.truncate(first);
+        self.steps.extend(new_steps);
+        self.steps.extend(reused);
+        self.file = file;
+        rescanned
+    }
+
+    /// The tokens, stripping out comments. See `Tokenizer::tokenize`.
+    pub fn tokenize(&self) -> impl Iterator<Item = Result<TokenInfo<'_>, TokenError<'_>>> + '_ {
+        self.tokenize_keep_comments().filter_map(|elem| match elem {
+            Ok(TokenOrComment::Token(t)) => Some(Ok(t)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+    }
+
+    /// The tokens and comments. See `Tokenizer::tokenize_keep_comments`.
+    pub fn tokenize_keep_comments(
+        &self,
+    ) -> impl Iterator<Item = Result<TokenOrComment<'_>, TokenError<'_>>> + '_ {
+        let lexer = &self.tokenizer.lexer;
+        let file = &self.file;
+        let lexemes = self.steps.iter().filter_map(move |step| {
+            let start = file.position(step.start);
+            Some(match step.kind {
+                StepKind::Match {
+                    rule,
+                    end_inclusive,
+                } => {
+                    if lexer.rule(rule).action == Action::Skip {
+                        return None;
+                    }
+                    let end_inclusive = file.position(end_inclusive);
+                    Ok(Lexeme {
+                        rule,
+                        start,
+                        end_inclusive,
+                        text: text(file, start, end_inclusive),
+                    })
+                }
+                StepKind::NotAToken { end } => Err(TokenError::NotAToken {
+                    start,
+                    end: file.position(end),
+                }),
+                StepKind::NonAsciiChar { pos } => {
+                    let pos = file.position(pos);
+                    let c = pos.source_char();
+                    Err(TokenError::NonAsciiChar { c, pos })
+                }
+                StepKind::UnclosedComment { end } => Err(TokenError::UnclosedComment {
+                    start,
+                    end: file.position(end),
+                }),
+                StepKind::BadUnicodeEscape { pos } => {
+                    Err(unicode::decode(file.position(pos)).unwrap_err())
+                }
+            })
+        });
+        self.tokenizer.elems(lexemes)
+    }
+}
+
+impl Step {
+    fn new(scanned: Scanned<RuleId>) -> Self {
+        let kind = match scanned.result {
+            Ok(m) => StepKind::Match {
+                rule: *m.label,
+                end_inclusive: m.end_inclusive.offset(),
+            },
+            Err(TokenError::NotAToken { end, .. }) => StepKind::NotAToken { end: end.offset() },
+            Err(TokenError::NonAsciiChar { pos, .. }) => {
+                StepKind::NonAsciiChar { pos: pos.offset() }
+            }
+            Err(TokenError::UnclosedComment { end, .. }) => {
+                StepKind::UnclosedComment { end: end.offset() }
+            }
+            Err(TokenError::BadUnicodeEscape { start, .. }) => StepKind::BadUnicodeEscape {
+                pos: start.offset(),
+            },
+            Err(e) => unreachable!("not a scanning error: {:?}", e),
+        };
+        Self {
+            start: scanned.start.offset(),
+            read_to: scanned.read_to,
+            kind,
+        }
+    }
+
+    /// Move the step, by applying `shift` to all its offsets.
+    fn shifted(&self, shift: impl Fn(usize) -> usize) -> Self {
+        let kind = match self.kind {
+            StepKind::Match {
+                rule,
+                end_inclusive,
+            } => StepKind::Match {
+                rule,
+                end_inclusive: shift(end_inclusive),
+            },
+            StepKind::NotAToken { end } => StepKind::NotAToken { end: shift(end) },
+            StepKind::NonAsciiChar { pos } => StepKind::NonAsciiChar { pos: shift(pos) },
+            StepKind::UnclosedComment { end } => StepKind::UnclosedComment { end: shift(end) },
+            StepKind::BadUnicodeEscape { pos } => StepKind::BadUnicodeEscape { pos: shift(pos) },
+        };
+        Self {
+            start: shift(self.start),
+            read_to: match self.read_to {
+                usize::MAX => usize::MAX,
+                read_to => shift(read_to),
+            },
+            kind,
+        }
+    }
+}
+
+/// The positions of `file`, from byte offset `offset` onwards.
+fn positions_from(file: &SourceFile, offset: usize) -> impl Iterator<Item = Position<'_>> + Clone {
+    let first_line = file.position(offset).line_num;
+    (first_line..file.num_lines())
+        .flat_map(move |n| line_positions(file.id(), n, file.line_start(n), file.line(n)))
+        .skip_while(move |pos| pos.offset() < offset)
+}
+
+/// The text from `start` up to and including `end_inclusive`, the same as `Lexer::lex` would give.
+fn text<'a>(
+    file: &'a SourceFile,
+    start: Position<'a>,
+    end_inclusive: Position<'a>,
+) -> Cow<'a, str> {
+    let end = end_inclusive.offset() + end_inclusive.width();
+    if start.line_num == end_inclusive.line_num {
+        return Cow::Borrowed(&start.line[start.col..end - start.line_start]);
+    }
+    let text = &file.src()[start.offset()..end];
+    Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Rng;
+    use crate::tokenizer::Text;
+
+    /// Check the tokens against tokenizing from scratch.
+    fn check(tokens: &IncrementalTokens) {
+        let tokenizer = tokens.tokenizer;
+        let expected: Vec<_> = tokenizer
+            .tokenize_keep_comments(Text(tokens.src()))
+            .map(|elem| format!("{:?}", elem))
+            .collect();
+        let actual: Vec<_> = tokens
+            .tokenize_keep_comments()
+            .map(|elem| format!("{:?}", elem))
+            .collect();
+        assert_eq!(expected, actual, "{:?}", tokens.src());
+    }
+
+    /// Apply an edit, given as the text around it: `before`, then `old` is replaced by `new`.
+    fn edit(tokens: &mut IncrementalTokens, before: &str, old: &str, new: &str) -> Range<usize> {
+        let start = tokens.src().find(before).expect(before) + before.len();
+        assert!(tokens.src()[start..].starts_with(old));
+        let rescanned = tokens.edit(start..start + old.len(), new);
+        check(tokens);
+        rescanned
+    }
+
+    const SRC: &str = "\
+class A {
+    /** Doc. */
+    public int f() {
+        int x = 1; // one
+        String s = \"a b\";
+        return -2147483648;
+    }
+
+    public int g() { return 2; }
+}
+";
+
+    #[test]
+    fn local_edits() {
+        let tokenizer = Tokenizer::new();
+        let mut tokens = tokenizer.tokenize_incremental(SRC);
+        check(&tokens);
+
+        // Only the edited line is scanned again.
+        let line = "        int x = 1; // one\n";
+        let line_start = SRC.find(line).unwrap();
+        let rescanned = edit(&mut tokens, "int x", "", "yz");
+        assert!(rescanned.start >= line_start - 1, "{:?}", rescanned);
+        assert!(
+            rescanned.end <= line_start + line.len() + 2,
+            "{:?}",
+            rescanned
+        );
+
+        edit(&mut tokens, "int xyz = ", "1", "12345");
+        edit(&mut tokens, "String s = \"a", " ", "");
+        edit(&mut tokens, "return ", "-", "");
+        edit(&mut tokens, "return ", "", "-");
+        edit(&mut tokens, "class A {", "\n", "\r\n");
+        edit(&mut tokens, "public int g() { return 2; }", "\n}\n", "\n}");
+        edit(
+            &mut tokens,
+            "public int g() { return 2; }\n}",
+            "",
+            " // end",
+        );
+    }
+
+    /// Edits that change how the rest of the file is tokenized.
+    #[test]
+    fn far_reaching_edits() {
+        let tokenizer = Tokenizer::new();
+        let mut tokens = tokenizer.tokenize_incremental(SRC);
+
+        // An unclosed comment swallows the rest of the file, until it's closed again.
+        let rescanned = edit(&mut tokens, "int f() {", "", " /*");
+        assert_eq!(tokens.src().len(), rescanned.end);
+        assert_eq!(1, tokens.tokenize().filter(Result::is_err).count());
+        // Closing it has to re-scan the rest of the file too, since there were no tokens left to
+        // reuse. But after that, edits are local again.
+        edit(&mut tokens, "int x = 1;", "", " */");
+        assert_eq!(0, tokens.tokenize().filter(Result::is_err).count());
+        let rescanned = edit(&mut tokens, "return 2", "", "2");
+        assert!(rescanned.end < tokens.src().len());
+
+        // So does an unclosed string, up to the end of the line.
+        edit(&mut tokens, "String s = ", "\"", "");
+        edit(&mut tokens, "String s = ", "", "\"");
+
+        // Unicode escapes, and breaking them up.
+        edit(&mut tokens, "String ", "s", "\\u0073");
+        edit(&mut tokens, "String \\", "", "\\");
+        edit(&mut tokens, "String \\", "\\", "");
+        edit(&mut tokens, "String \\u00", "73", "");
+        edit(&mut tokens, "String \\u00", "", "73");
+
+        // Doc comments attach to the next token.
+        edit(&mut tokens, "/** Doc. */", "\n    public", "");
+        edit(&mut tokens, "/**", "", "/");
+
+        // Non-ASCII, and starting from and going back to nothing.
+        edit(&mut tokens, "class A", "", "é");
+        let len = tokens.src().len();
+        tokens.edit(0..len, "");
+        check(&tokens);
+        edit(&mut tokens, "", "", "int x;");
+    }
+
+    /// Many small edits in a row, each checked against tokenizing from scratch.
+    #[test]
+    fn many_edits() {
+        let tokenizer = Tokenizer::new();
+        let mut tokens = tokenizer.tokenize_incremental(SRC);
+
+        let snippets = [
+            "/*", "*/", "\"", "'", "\n", "\r", "\\u0041", "x", " ", "//", "-1", "é",
+        ];
+        let mut rng = Rng::new(12345);
+        for _ in 0..300 {
+            let src = tokens.src();
+            let mut start = rng.below(src.len() + 1);
+            while !src.is_char_boundary(start) {
+                start -= 1;
+            }
+            let mut end = (start + rng.below(4)).min(src.len());
+            while !src.is_char_boundary(end) {
+                end += 1;
+            }
+            let text = snippets[rng.below(snippets.len())];
+            tokens.edit(start..end, text);
+            check(&tokens);
+        }
+    }
+}
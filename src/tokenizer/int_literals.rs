@@ -0,0 +1,157 @@
+//! Range checking for int literals.
+//!
+//! The tokenizer reads int literals as digit strings, since whether one is in range depends on its
+//! context: `2147483648` (2^31) is only allowed as the operand of unary minus, so that the smallest
+//! int can be written as `-2147483648`.
+//!
+//! Whether a `-` is unary can be told from the token before it: after the end of an operand (a
+//! name, a literal, `this`, `)` or `]`), it's binary. The exception is a cast to a primitive type,
+//! like `(int) -2147483648`, where the minus after `)` is unary. (A minus after a cast to a
+//! reference type, like `(Integer) -1`, is binary, just like in Java.)
+
+use crate::tokenizer::token_types::{Keyword, Literal, Operator, Separator};
+use crate::tokenizer::tokens::{Token, TokenError, TokenInfo};
+use std::convert::TryInto;
+
+/// The value of the int literal `digits`, or of `-digits` if `negated`. `None` if it's out of
+/// range.
+pub fn int_value(digits: &str, negated: bool) -> Option<i32> {
+    // Note that in Joos 1W, all int literals are `int` type, since there is no `unsigned` in Java,
+    // and no `long` in Joos 1W.
+    let n: i64 = digits.parse().ok()?;
+    let n = if negated { -n } else { n };
+    n.try_into().ok()
+}
+
+/// Checks the int literals in a stream of tokens, one token at a time.
+#[derive(Debug, Clone, Default)]
+pub struct IntLiteralChecker {
+    /// The last few tokens, most recent last.
+    recent: [Recent; 3],
+}
+
+/// What we need to remember about a recent token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Recent {
+    #[default]
+    Other,
+    /// The end of an operand, other than `)`.
+    Operand,
+    LParen,
+    RParen,
+    PrimitiveType,
+    UnaryMinus,
+}
+
+impl IntLiteralChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check the next token. It's an error if it's an int literal that's out of range.
+    pub fn check<'a>(&mut self, token: &TokenInfo<'a>) -> Result<(), TokenError<'a>> {
+        let next = match &token.val {
+            Token::Literal(Literal::Int(digits)) => {
+                let negated = self.recent[2] == Recent::UnaryMinus;
+                self.push(Recent::Operand);
+                if int_value(digits, negated).is_some() {
+                    return Ok(());
+                }
+
+                let (start, lexeme) = (token.start, token.lexeme);
+                return Err(if int_value(digits, true).is_some() {
+                    TokenError::MinIntNotNegated { start, lexeme }
+                } else {
+                    TokenError::IntTooLarge { start, lexeme }
+                });
+            }
+            Token::Operator(Operator::Minus) => match self.recent {
+                [.., Recent::Operand] => Recent::Other,
+                [Recent::LParen, Recent::PrimitiveType, Recent::RParen] => Recent::UnaryMinus,
+                [.., Recent::RParen] => Recent::Other,
+                _ => Recent::UnaryMinus,
+            },
+            Token::Identifier(_) | Token::Literal(_) | Token::Keyword(Keyword::This) => {
+                Recent::Operand
+            }
+            Token::Keyword(k) if is_primitive_type(*k) => Recent::PrimitiveType,
+            Token::Separator(Separator::LParen) => Recent::LParen,
+            Token::Separator(Separator::RParen) => Recent::RParen,
+            Token::Separator(Separator::RBracket) => Recent::Operand,
+            _ => Recent::Other,
+        };
+        self.push(next);
+        Ok(())
+    }
+
+    fn push(&mut self, recent: Recent) {
+        self.recent.rotate_left(1);
+        self.recent[2] = recent;
+    }
+}
+
+fn is_primitive_type(k: Keyword) -> bool {
+    use Keyword::*;
+    matches!(
+        k,
+        Boolean | Byte | Char | Short | Int | Long | Float | Double
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::{Text, Tokenizer};
+
+    #[test]
+    fn values() {
+        for (digits, negated, expected) in [
+            ("0", false, Some(0)),
+            ("0", true, Some(0)),
+            ("2147483647", false, Some(i32::MAX)),
+            ("2147483647", true, Some(-i32::MAX)),
+            ("2147483648", false, None),
+            ("2147483648", true, Some(i32::MIN)),
+            ("2147483649", true, None),
+            ("99999999999999999999999", true, None),
+        ] {
+            assert_eq!(
+                expected,
+                int_value(digits, negated),
+                "{} {}",
+                digits,
+                negated
+            );
+        }
+    }
+
+    /// Which `2147483648`s are negated.
+    #[test]
+    fn contexts() {
+        let tokenizer = Tokenizer::new();
+        for (src, ok) in [
+            ("-2147483648", true),
+            ("x = -2147483648;", true),
+            ("x = - 2147483648;", true),
+            ("f(-2147483648, -2147483648)", true),
+            ("return -2147483648;", true),
+            ("x = 1 - -2147483648;", true),
+            ("x = -(2147483648);", false),
+            ("x = (int) -2147483648;", true),
+            ("x = (Integer) -2147483648;", false),
+            ("x = (y) -2147483648;", false),
+            ("x = y -2147483648;", false),
+            ("x = 1-2147483648;", false),
+            ("x = a[0] -2147483648;", false),
+            ("x = this -2147483648;", false),
+            ("x = 2147483648;", false),
+            ("x = --2147483648;", false),
+        ] {
+            let (_, errors) = tokenizer.tokenize_all(Text(src));
+            assert_eq!(ok, errors.is_empty(), "{}", src);
+            if !ok {
+                assert!(matches!(errors[..], [TokenError::MinIntNotNegated { .. }]));
+            }
+        }
+    }
+}
@@ -0,0 +1,627 @@
+//! A lexer generator: build a tokenizer for any small language from a declarative spec.
+//!
+//! A spec lists named rules in priority order, one per line:
+//! ```text
+//! # Comments start with `#`.
+//! skip WHITESPACE [ \t\n]+
+//! comment COMMENT #[^\n]*
+//! keep if if
+//! keep IDENTIFIER [a-z]+
+//! ```
+//! Each rule is `<action> <name> <pattern>`, where the pattern is the rest of the line, in the
+//! syntax of `regex`. The action is one of:
+//! - `keep`: report matches of the rule.
+//! - `skip`: silently drop matches, e.g. for whitespace.
+//! - `comment`: report matches, but if the input runs out in the middle of one, report an
+//!   `UnclosedComment` error.
+//!
+//! The lexer runs "max munch": it always takes the longest match, and if two rules match the same
+//! longest prefix, the earlier one wins. So e.g. `if` above is a keyword, not an `IDENTIFIER`.
+//!
+//! ```
+//! # use cs444::tokenizer::lexer::{Lexer, LexerSpec};
+//! # use cs444::tokenizer::Text;
+//! let spec = LexerSpec::parse("skip WS [ \\n]+\nkeep NUM [0-9]+\nkeep PLUS \\+").unwrap();
+//! let lexer = Lexer::new(spec);
+//!
+//! let kinds: Vec<_> = lexer
+//!     .lex(Text("1 + 23"))
+//!     .map(|l| lexer.rule(l.unwrap().rule).name.as_str())
+//!     .collect();
+//! assert_eq!(vec!["NUM", "PLUS", "NUM"], kinds);
+//! ```
+
+use crate::source_map::Span;
+use crate::tokenizer::dfa::{CommentStates, CompiledDFA, Scanned};
+use crate::tokenizer::dump::Dump;
+use crate::tokenizer::nfa::NFA;
+use crate::tokenizer::regex::Regex;
+use crate::tokenizer::states::State;
+use crate::tokenizer::tokens::TokenError;
+use crate::tokenizer::{all_positions, DfaStats, Input, Position};
+use std::borrow::Cow;
+use std::collections::HashMap as Map;
+use std::fmt;
+
+pub use crate::tokenizer::regex::RegexError;
+
+/// What to do with a match of a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Keep,
+    Skip,
+    /// Like `Keep`, but running out of input in the middle of a match is an error.
+    Comment,
+}
+
+impl Action {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "keep" => Some(Action::Keep),
+            "skip" => Some(Action::Skip),
+            "comment" => Some(Action::Comment),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// Unique within a spec. Has no whitespace.
+    pub name: String,
+    pub action: Action,
+    pub pattern: String,
+}
+
+/// Identifies a rule by its position in the spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RuleId(u32);
+
+impl RuleId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// The rules of a lexer, in priority order.
+#[derive(Debug, Clone, Default)]
+pub struct LexerSpec {
+    rules: Vec<Rule>,
+    /// The parsed pattern of each rule.
+    regexes: Vec<Regex>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecError {
+    /// The line of the spec with the error. One-indexed. `None` for rules added by `add_rule`.
+    pub line: Option<usize>,
+    pub kind: SpecErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecErrorKind {
+    /// A line that isn't `<action> <name> <pattern>`.
+    Malformed,
+    UnknownAction(String),
+    DuplicateName(String),
+    BadPattern {
+        name: String,
+        error: RegexError,
+    },
+    /// Max munch can't handle empty matches, since it would never make progress.
+    MatchesEmpty(String),
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        match &self.kind {
+            SpecErrorKind::Malformed => write!(f, "expected `<action> <name> <pattern>`"),
+            SpecErrorKind::UnknownAction(action) => write!(f, "unknown action `{}`", action),
+            SpecErrorKind::DuplicateName(name) => write!(f, "duplicate rule `{}`", name),
+            SpecErrorKind::BadPattern { name, error } => {
+                write!(f, "bad pattern for `{}`: {}", name, error)
+            }
+            SpecErrorKind::MatchesEmpty(name) => {
+                write!(f, "pattern for `{}` matches the empty string", name)
+            }
+        }
+    }
+}
+
+impl LexerSpec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a spec in the format described at the top of this module.
+    pub fn parse(text: &str) -> Result<Self, SpecError> {
+        let mut spec = Self::new();
+        for (i, line) in text.lines().enumerate() {
+            let err = |kind| SpecError {
+                line: Some(i + 1),
+                kind,
+            };
+
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(3, ' ');
+            let (action, name, pattern) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(action), Some(name), Some(pattern)) if !name.is_empty() => {
+                    (action, name, pattern)
+                }
+                _ => return Err(err(SpecErrorKind::Malformed)),
+            };
+            let action = Action::parse(action)
+                .ok_or_else(|| err(SpecErrorKind::UnknownAction(String::from(action))))?;
+
+            spec.add_rule(name, action, pattern)
+                .map_err(|e| err(e.kind))?;
+        }
+        Ok(spec)
+    }
+
+    /// Add a rule, with lower priority than all the existing ones.
+    pub fn add_rule(
+        &mut self,
+        name: &str,
+        action: Action,
+        pattern: &str,
+    ) -> Result<RuleId, SpecError> {
+        let err = |kind| SpecError { line: None, kind };
+
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(err(SpecErrorKind::Malformed));
+        }
+        if self.rule_id(name).is_some() {
+            return Err(err(SpecErrorKind::DuplicateName(String::from(name))));
+        }
+        let regex = Regex::parse(pattern).map_err(|error| {
+            err(SpecErrorKind::BadPattern {
+                name: String::from(name),
+                error,
+            })
+        })?;
+        if regex.matches_empty() {
+            return Err(err(SpecErrorKind::MatchesEmpty(String::from(name))));
+        }
+
+        self.rules.push(Rule {
+            name: String::from(name),
+            action,
+            pattern: String::from(pattern),
+        });
+        self.regexes.push(regex);
+        Ok(RuleId(self.rules.len() as u32 - 1))
+    }
+
+    /// All the rules, in priority order. Indexed by `RuleId::index`.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn rule_id(&self, name: &str) -> Option<RuleId> {
+        let i = self.rules.iter().position(|r| r.name == name)?;
+        Some(RuleId(i as u32))
+    }
+
+    /// Dump the NFA for this spec, from the states reachable after reading `prefix`. Accepted
+    /// states are labelled with rule names. See `dump`.
+    pub fn dump_nfa(&self, prefix: &str) -> Option<Dump> {
+        self.nfa()
+            .dump(prefix, |id| self.rules[id.index()].name.clone())
+    }
+
+    /// Like `dump_nfa`, but for the minimized DFA. This builds the DFA from scratch, so it's slow.
+    pub fn dump_dfa(&self, prefix: &str) -> Option<Dump> {
+        let dfa = self.nfa().to_dfa().minimize();
+        dfa.dump(prefix, |id| self.rules[id.index()].name.clone())
+    }
+
+    /// An NFA that accepts a match of each rule with the rule's id.
+    fn nfa(&self) -> NFA<State, RuleId> {
+        let mut nfa = NFA {
+            init: State(0),
+            accepted: Map::new(),
+            delta: Map::new(),
+            epsilon: Map::new(),
+        };
+
+        // Each rule's states are numbered after all the previous rules' states. When a DFA state
+        // contains accepted states from several rules, the smallest one wins, which gives earlier
+        // rules priority.
+        let mut num_states = 1;
+        let mut new_state = || {
+            num_states += 1;
+            State(num_states - 1)
+        };
+        for (i, regex) in self.regexes.iter().enumerate() {
+            let (start, end) = regex.add_to_nfa(&mut nfa, &mut new_state);
+            nfa.epsilon.entry(nfa.init).or_default().push(start);
+            nfa.accepted.insert(end, RuleId(i as u32));
+        }
+        nfa
+    }
+}
+
+/// A tokenizer generated from a `LexerSpec`.
+#[derive(Debug)]
+pub struct Lexer {
+    spec: LexerSpec,
+    dfa: CompiledDFA<RuleId>,
+    comments: CommentStates,
+    stats: DfaStats,
+}
+
+/// A match of one of the lexer's rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lexeme<'a> {
+    pub rule: RuleId,
+    pub start: Position<'a>,
+    /// Inclusive! May be on a later line than `start`.
+    pub end_inclusive: Position<'a>,
+    /// The source text. This is only allocated if the lexeme spans several lines, in which case
+    /// each line terminator is written as `\n`. A terminator at the very end isn't included.
+    pub text: Cow<'a, str>,
+}
+
+impl Lexeme<'_> {
+    /// The lexeme's location in its source file.
+    pub fn span(&self) -> Span {
+        self.start.span_to(self.end_inclusive)
+    }
+}
+
+impl Lexer {
+    /// Compile the spec into a minimal DFA.
+    ///
+    /// This can be expensive for big specs. To avoid paying for it at runtime, dump the DFA with
+    /// `to_table`, and load it back with `from_table`.
+    pub fn new(spec: LexerSpec) -> Self {
+        let raw = spec.nfa().to_dfa();
+        let dfa = raw.minimize().compile();
+        let raw_states = raw.num_states();
+        Self::from_dfa(spec, dfa, Some(raw_states))
+    }
+
+    /// Load a DFA dumped by `to_table`. `spec` must be the spec it was built from.
+    pub fn from_table(spec: LexerSpec, table: &str) -> Result<Self, String> {
+        let dfa = CompiledDFA::from_table(table, |name| spec.rule_id(name))?;
+        Ok(Self::from_dfa(spec, dfa, None))
+    }
+
+    fn from_dfa(spec: LexerSpec, dfa: CompiledDFA<RuleId>, raw_states: Option<usize>) -> Self {
+        let comments = dfa.comment_states(|id| spec.rules[id.index()].action == Action::Comment);
+        let stats = DfaStats {
+            raw_states,
+            minimized_states: dfa.num_states(),
+            minimized_transitions: dfa.num_transitions(),
+            symbol_classes: dfa.num_classes(),
+        };
+        Self {
+            spec,
+            dfa,
+            comments,
+            stats,
+        }
+    }
+
+    /// Dump the DFA in the format of `dfa::table`. Accepted states are labelled with rule names.
+    pub fn to_table(&self) -> String {
+        self.dfa.to_table(|id| self.rule(*id).name.clone())
+    }
+
+    pub fn spec(&self) -> &LexerSpec {
+        &self.spec
+    }
+
+    pub fn rule(&self, id: RuleId) -> &Rule {
+        &self.spec.rules[id.index()]
+    }
+
+    pub fn dfa_stats(&self) -> DfaStats {
+        self.stats
+    }
+
+    /// Run the DFA over the positions, keeping matches of every rule. See `CompiledDFA::scan`.
+    pub(super) fn scan<'a>(
+        &'a self,
+        positions: impl Iterator<Item = Position<'a>> + Clone + 'a,
+    ) -> impl Iterator<Item = Scanned<'a, RuleId>> + 'a {
+        self.dfa.scan(positions, &self.comments)
+    }
+
+    /// Split the input into lexemes, dropping matches of `skip` rules.
+    ///
+    /// Errors are reported in-line, and scanning resumes after each one.
+    pub fn lex<'a>(
+        &'a self,
+        input: impl Input<'a>,
+    ) -> impl Iterator<Item = Result<Lexeme<'a>, TokenError<'a>>> + 'a {
+        let file = input.file();
+        let lines = input.lines();
+        // Lexemes come in order, so one pass over the lines is enough to find their text.
+        let mut cursor = lines.clone().enumerate();
+
+        self.dfa
+            .tokenize(all_positions(file, lines), &self.comments)
+            .filter_map(move |result| match result {
+                Ok(m) if self.rule(*m.label).action == Action::Skip => None,
+                Ok(m) => Some(Ok(Lexeme {
+                    rule: *m.label,
+                    start: m.start,
+                    end_inclusive: m.end_inclusive,
+                    text: text(m.start, m.end_inclusive, &mut cursor),
+                })),
+                Err(e) => Some(Err(e)),
+            })
+    }
+}
+
+/// The text from `start` up to and including `end_inclusive`.
+///
+/// `lines` is used to find the middle lines of a multi-line lexeme. It must not be past the line of
+/// `start` yet, and it's left right after the line of `end_inclusive`.
+fn text<'a>(
+    start: Position<'a>,
+    end_inclusive: Position<'a>,
+    lines: &mut impl Iterator<Item = (usize, (usize, &'a str))>,
+) -> Cow<'a, str> {
+    let end = end_inclusive.col + end_inclusive.width();
+    if start.line_num == end_inclusive.line_num {
+        return Cow::Borrowed(&start.line[start.col..end]);
+    }
+
+    let mut text = String::from(&start.line[start.col..]);
+    for (line_num, (_, line)) in lines {
+        if line_num <= start.line_num {
+            continue;
+        }
+        text.push('\n');
+        if line_num == end_inclusive.line_num {
+            text.push_str(&line[..end]);
+            break;
+        }
+        text.push_str(line);
+    }
+    Cow::Owned(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Rng;
+    use crate::tokenizer::states::Symbol;
+    use crate::tokenizer::{Dialect, Text};
+    use std::ops::Range;
+
+    /// A lexer for a tiny language of test-spec files.
+    const SPEC: &str = "\
+# Comments and blank lines are ignored.
+
+skip WHITESPACE [ \\t\\n]+
+comment COMMENT \\{[^}]*\\}
+keep expect expect
+keep NAME [a-z][a-z_]*
+keep NUMBER -?[0-9]+
+keep ARROW ->
+";
+
+    fn lex(lexer: &Lexer, input: &str) -> Vec<(String, String)> {
+        lexer
+            .lex(Text(input))
+            .map(|l| {
+                let l = l.unwrap();
+                let name = lexer.rule(l.rule).name.clone();
+                (name, l.text.into_owned())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn small_language() {
+        let lexer = Lexer::new(LexerSpec::parse(SPEC).unwrap());
+        assert_eq!(6, lexer.spec().rules().len());
+
+        let actual = lex(&lexer, "expect foo -> -12 {hi\r\n\nthere}\nexpected");
+        let expected = [
+            ("expect", "expect"),
+            ("NAME", "foo"),
+            ("ARROW", "->"),
+            ("NUMBER", "-12"),
+            // Line terminators are normalized.
+            ("COMMENT", "{hi\n\nthere}"),
+            // Max munch, so this isn't `expect` followed by `ed`.
+            ("NAME", "expected"),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(name, text)| (String::from(name), String::from(text)))
+            .collect();
+        assert_eq!(expected, actual);
+
+        let errors: Vec<_> = lexer
+            .lex(Text("foo # bar {unclosed"))
+            .filter_map(Result::err)
+            .collect();
+        assert!(matches!(errors[0], TokenError::NotAToken { .. }));
+        assert!(matches!(errors[1], TokenError::UnclosedComment { .. }));
+        assert_eq!(2, errors.len());
+    }
+
+    #[test]
+    fn table_round_trip() {
+        let lexer = Lexer::new(LexerSpec::parse(SPEC).unwrap());
+        let table = lexer.to_table();
+
+        let loaded = Lexer::from_table(LexerSpec::parse(SPEC).unwrap(), &table).unwrap();
+        assert_eq!(lexer.dfa, loaded.dfa);
+        assert_eq!(lexer.comments, loaded.comments);
+
+        // The labels must name rules in the spec.
+        let other = LexerSpec::parse("keep X x").unwrap();
+        assert!(Lexer::from_table(other, &table).is_err());
+    }
+
+    #[test]
+    fn spec_errors() {
+        for (spec, expected) in [
+            ("keep A", "line 1: expected `<action> <name> <pattern>`"),
+            ("\nkeep  a", "line 2: expected `<action> <name> <pattern>`"),
+            ("drop A a", "line 1: unknown action `drop`"),
+            ("keep A a\nskip A b", "line 2: duplicate rule `A`"),
+            (
+                "keep A (a",
+                "line 1: bad pattern for `A`: unexpected end of pattern",
+            ),
+            (
+                "keep A a*",
+                "line 1: pattern for `A` matches the empty string",
+            ),
+        ] {
+            let actual = LexerSpec::parse(spec).unwrap_err().to_string();
+            assert_eq!(expected, actual, "{:?}", spec);
+        }
+
+        let mut spec = LexerSpec::new();
+        assert_eq!(Ok(RuleId(0)), spec.add_rule("A", Action::Keep, "a"));
+        let err = spec.add_rule("B C", Action::Keep, "b").unwrap_err();
+        assert_eq!(
+            SpecError {
+                line: None,
+                kind: SpecErrorKind::Malformed
+            },
+            err
+        );
+    }
+
+    /// Split `input` by max munch in a loop. Where nothing matches, skip a single symbol.
+    fn munch_all<'l>(
+        input: &[Symbol],
+        longest_match: impl Fn(&[Symbol]) -> Option<(usize, &'l RuleId)>,
+    ) -> Vec<(Range<usize>, Option<RuleId>)> {
+        let mut matches = vec![];
+        let mut start = 0;
+        while start < input.len() {
+            let (len, rule) = match longest_match(&input[start..]) {
+                Some((len, &rule)) => (len, Some(rule)),
+                None => (1, None),
+            };
+            matches.push((start..start + len, rule));
+            start += len;
+        }
+        matches
+    }
+
+    /// Run the NFA of each spec directly, and check that the checked-in DFA finds the same
+    /// matches on random Java-ish inputs. This catches bugs in `nfa_to_dfa`, `minimize`,
+    /// `compile`, and the table format.
+    #[test]
+    fn nfa_and_dfa_agree() {
+        let fragments = [
+            "class",
+            "classy",
+            "if",
+            "instanceof",
+            "x",
+            "_a$1",
+            "0",
+            "00",
+            "017",
+            "09",
+            "123",
+            "2147483648",
+            "0x",
+            "0x1F",
+            "0XaL",
+            "10L",
+            "1.",
+            ".5",
+            "1.5e3",
+            "1e",
+            "1e+",
+            "1E-3f",
+            "2d",
+            "..",
+            ".",
+            "\"\"",
+            "\"a\\\"b\"",
+            "\"\\u0041\"",
+            "\"unclosed",
+            "'c'",
+            "'\\''",
+            "'\\n'",
+            "''",
+            "'",
+            "/*",
+            "*/",
+            "/**/",
+            "/***/",
+            "/*/",
+            "/** doc */",
+            "//",
+            "// line\n",
+            "=",
+            "==",
+            "!=",
+            "<",
+            "<<",
+            "<<=",
+            ">",
+            ">>",
+            ">>>",
+            ">>>=",
+            "+",
+            "++",
+            "+=",
+            "-",
+            "--",
+            "&&",
+            "&",
+            "|",
+            "||",
+            "^=",
+            "~",
+            "?",
+            ":",
+            "(",
+            ")",
+            "{",
+            "}",
+            "[",
+            "]",
+            ";",
+            ",",
+            "@",
+            "#",
+            "\\",
+            "é",
+            " ",
+            "\t",
+            "\u{c}",
+            "\n",
+            "\r\n",
+            "\r",
+        ];
+        let mut rng = Rng::new(2024);
+
+        for dialect in [Dialect::Joos1W, Dialect::Java1_3] {
+            let nfa = dialect.spec().nfa();
+            let lexer = Lexer::from_table(dialect.spec(), dialect.table()).unwrap();
+
+            for _ in 0..100 {
+                let input: String = (0..rng.below(40))
+                    .map(|_| fragments[rng.below(fragments.len())])
+                    .collect();
+                let symbols: Vec<_> = input.chars().map(Symbol::from_char).collect();
+
+                let expected = munch_all(&symbols, |s| nfa._longest_match(s.iter().copied()));
+                let actual = munch_all(&symbols, |s| lexer.dfa._longest_match(s.iter().copied()));
+                assert_eq!(expected, actual, "{}: {:?}", dialect, input);
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! A lossless view of a token stream: every token carries the whitespace and comments ("trivia")
+//! around it, so the exact source can be rebuilt, byte for byte. See `Tokenizer::tokenize_lossless`.
+//!
+//! Trivia is split between tokens the usual way (as in e.g. Roslyn or rust-analyzer):
+//! - A token's trailing trivia is everything after it on the same line, up to and including the
+//!   line terminator.
+//! - Its leading trivia is everything else since the previous token's trailing trivia.
+//!
+//! So for
+//! ```text
+//! int x; // x
+//!
+//! /** Doc. */ int y;
+//! ```
+//! the leading trivia of the second `int` is the blank line and the doc comment, plus the space
+//! after it. The trailing trivia of the first `;` is ` `, `// x` and the newline.
+
+use crate::source_map::{FileId, Span};
+use crate::tokenizer::tokens::{TokenInfo, TokenOrComment};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces, tabs, and form feeds. Never contains a line terminator.
+    Whitespace,
+    /// A single line terminator: CR, LF, or CR LF.
+    Newline,
+    LineComment,
+    BlockComment,
+    DocComment,
+}
+
+/// A piece of whitespace, or a comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia<'a> {
+    pub kind: TriviaKind,
+    /// Exactly as in the source, including line terminators and untranslated unicode escapes.
+    pub text: &'a str,
+    pub span: Span,
+}
+
+/// A token, together with the trivia around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LosslessToken<'a> {
+    pub leading: Vec<Trivia<'a>>,
+    pub token: TokenInfo<'a>,
+    pub trailing: Vec<Trivia<'a>>,
+}
+
+/// A whole file as a lossless token stream. `Display` rebuilds the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LosslessFile<'a> {
+    pub tokens: Vec<LosslessToken<'a>>,
+    /// The trivia after the last token's trailing trivia. For a file without tokens, this is
+    /// everything.
+    pub end: Vec<Trivia<'a>>,
+}
+
+impl fmt::Display for LosslessFile<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let write_trivia = |f: &mut fmt::Formatter, trivia: &[Trivia]| {
+            trivia.iter().try_for_each(|t| f.write_str(t.text))
+        };
+
+        for t in &self.tokens {
+            write_trivia(f, &t.leading)?;
+            f.write_str(t.token.lexeme)?;
+            write_trivia(f, &t.trailing)?;
+        }
+        write_trivia(f, &self.end)
+    }
+}
+
+/// Builds a `LosslessFile` from the tokens and comments of `src`, in order.
+pub(super) struct Builder<'a> {
+    src: &'a str,
+    file: FileId,
+    /// The end of the last element.
+    offset: usize,
+    file_so_far: LosslessFile<'a>,
+    /// Trivia for the leading trivia of the next token.
+    leading: Vec<Trivia<'a>>,
+    /// Are we still in the trailing trivia of the last token?
+    in_trailing: bool,
+}
+
+impl<'a> Builder<'a> {
+    pub fn new(src: &'a str, file: FileId) -> Self {
+        Self {
+            src,
+            file,
+            offset: 0,
+            file_so_far: LosslessFile {
+                tokens: vec![],
+                end: vec![],
+            },
+            leading: vec![],
+            in_trailing: false,
+        }
+    }
+
+    pub fn push(&mut self, elem: TokenOrComment<'a>) {
+        let span = elem.span();
+        self.gap(span.lo as usize);
+
+        let kind = match elem {
+            TokenOrComment::Token(token) => {
+                self.file_so_far.tokens.push(LosslessToken {
+                    leading: std::mem::take(&mut self.leading),
+                    token,
+                    trailing: vec![],
+                });
+                self.in_trailing = true;
+                self.offset = span.hi as usize;
+                return;
+            }
+            TokenOrComment::LineComment(_) => TriviaKind::LineComment,
+            TokenOrComment::BlockComment(_) => TriviaKind::BlockComment,
+            TokenOrComment::DocComment(_) => TriviaKind::DocComment,
+        };
+        self.trivia(kind, span.lo as usize, span.hi as usize);
+    }
+
+    pub fn finish(mut self) -> LosslessFile<'a> {
+        self.gap(self.src.len());
+        self.file_so_far.end = self.leading;
+        self.file_so_far
+    }
+
+    /// Add trivia for the whitespace from the end of the last element up to `end`.
+    fn gap(&mut self, end: usize) {
+        while self.offset < end {
+            let rest = &self.src[self.offset..end];
+            let (kind, len) = if rest.starts_with("\r\n") {
+                (TriviaKind::Newline, 2)
+            } else if rest.starts_with(['\r', '\n']) {
+                (TriviaKind::Newline, 1)
+            } else {
+                let len = rest.find(['\r', '\n']).unwrap_or(rest.len());
+                (TriviaKind::Whitespace, len)
+            };
+            self.trivia(kind, self.offset, self.offset + len);
+        }
+    }
+
+    fn trivia(&mut self, kind: TriviaKind, lo: usize, hi: usize) {
+        let trivia = Trivia {
+            kind,
+            text: &self.src[lo..hi],
+            span: Span::new(self.file, lo, hi),
+        };
+        self.offset = hi;
+
+        match self.file_so_far.tokens.last_mut() {
+            Some(last) if self.in_trailing => {
+                last.trailing.push(trivia);
+                if kind == TriviaKind::Newline {
+                    self.in_trailing = false;
+                }
+            }
+            _ => self.leading.push(trivia),
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use crate::tokenizer::dfa::DFA;
+use crate::tokenizer::states::{State, Symbol};
+use std::collections::{HashMap as Map, HashSet as Set, VecDeque};
+use std::hash::Hash;
+
+impl<S: Clone + Eq + Hash, L: Clone + PartialEq> DFA<S, L> {
+    /// Produce an equivalent DFA with as few states as possible.
+    ///
+    /// Two states are merged only if they have the same label, so a state that accepts one token
+    /// type never merges with a state that accepts another. States that can't reach an accepting
+    /// state are merged into the implicit "dead" state, i.e. their transitions are dropped.
+    ///
+    /// The states of the result are numbered in BFS order from `init` (exploring symbols in order),
+    /// so the output doesn't depend on `HashMap` iteration order.
+    pub fn minimize(&self) -> DFA<State, L> {
+        let dfa = DenseDfa::new(self);
+        let blocks = dfa.partition();
+        dfa.merge(&blocks)
+    }
+
+    /// The number of states, not counting the implicit "dead" state.
+    pub fn num_states(&self) -> usize {
+        let mut states = Set::new();
+        states.insert(&self.init);
+        states.extend(self.accepted.keys());
+        for ((s, _), t) in &self.delta {
+            states.insert(s);
+            states.insert(t);
+        }
+        states.len()
+    }
+}
+
+/// A copy of a DFA with its states numbered `0..n`, plus an explicit dead state numbered `n`.
+///
+/// Every state has a transition on every symbol, which keeps the partition refinement simple.
+struct DenseDfa<'a, L> {
+    init: usize,
+    labels: Vec<Option<&'a L>>,
+    delta: Vec<[usize; Symbol::COUNT]>,
+}
+
+impl<'a, L: Clone + PartialEq> DenseDfa<'a, L> {
+    fn new<S: Clone + Eq + Hash>(dfa: &'a DFA<S, L>) -> Self {
+        // Number each state in the order we first see it.
+        let mut ids: Map<&S, usize> = Map::new();
+        let mut number = |s: &'a S| {
+            let next = ids.len();
+            *ids.entry(s).or_insert(next)
+        };
+
+        let init = number(&dfa.init);
+        let edges: Vec<_> = dfa
+            .delta
+            .iter()
+            .map(|((s, sym), t)| (number(s), *sym, number(t)))
+            .collect();
+        let accepted: Vec<_> = dfa
+            .accepted
+            .iter()
+            .map(|(s, label)| (number(s), label))
+            .collect();
+
+        let dead = ids.len();
+        let mut labels = vec![None; dead + 1];
+        let mut delta = vec![[dead; Symbol::COUNT]; dead + 1];
+        for (s, label) in accepted {
+            labels[s] = Some(label);
+        }
+        for (s, sym, t) in edges {
+            delta[s][sym.index()] = t;
+        }
+
+        Self {
+            init,
+            labels,
+            delta,
+        }
+    }
+
+    fn dead(&self) -> usize {
+        self.labels.len() - 1
+    }
+
+    /// Group equivalent states into blocks, using Moore's algorithm: start with one block per
+    /// label, then keep splitting blocks whose states disagree on which block some symbol leads to.
+    ///
+    /// Returns the block of each state.
+    fn partition(&self) -> Vec<usize> {
+        // Initially, partition by label. There are only a few hundred labels, so a linear search is
+        // fine (and it saves us from requiring labels to be hashable).
+        let mut distinct_labels: Vec<Option<&L>> = vec![None];
+        let mut blocks: Vec<usize> = self
+            .labels
+            .iter()
+            .map(
+                |label| match distinct_labels.iter().position(|l| l == label) {
+                    Some(i) => i,
+                    None => {
+                        distinct_labels.push(*label);
+                        distinct_labels.len() - 1
+                    }
+                },
+            )
+            .collect();
+        let mut num_blocks = distinct_labels.len();
+
+        loop {
+            // Two states stay in the same block iff they're in the same block now, and each symbol
+            // takes them to the same block.
+            let mut signatures: Map<(usize, Vec<usize>), usize> = Map::new();
+            let refined: Vec<usize> = (0..blocks.len())
+                .map(|s| {
+                    let targets = self.delta[s].iter().map(|&t| blocks[t]).collect();
+                    let next = signatures.len();
+                    *signatures.entry((blocks[s], targets)).or_insert(next)
+                })
+                .collect();
+
+            // Refinement only ever splits blocks, so if the count didn't change, nothing did.
+            if signatures.len() == num_blocks {
+                return blocks;
+            }
+            num_blocks = signatures.len();
+            blocks = refined;
+        }
+    }
+
+    /// Build the minimized DFA, with one state per block (except the dead block).
+    fn merge(&self, blocks: &[usize]) -> DFA<State, L> {
+        let dead_block = blocks[self.dead()];
+
+        // Pick a representative state for each block.
+        let mut representative = Map::new();
+        for (s, &b) in blocks.iter().enumerate() {
+            representative.entry(b).or_insert(s);
+        }
+
+        let mut dfa = DFA {
+            init: State(0),
+            accepted: Map::new(),
+            delta: Map::new(),
+        };
+
+        // Number the blocks in BFS order.
+        let mut numbering = Map::new();
+        numbering.insert(blocks[self.init], State(0));
+        let mut queue = VecDeque::new();
+        queue.push_back(blocks[self.init]);
+
+        while let Some(b) = queue.pop_front() {
+            let s = representative[&b];
+            let from = numbering[&b];
+            if let Some(label) = self.labels[s] {
+                dfa.accepted.insert(from, label.clone());
+            }
+
+            for sym in Symbol::all() {
+                let target = blocks[self.delta[s][sym.index()]];
+                if target == dead_block {
+                    continue;
+                }
+
+                let next = State(numbering.len() as u32);
+                let to = *numbering.entry(target).or_insert_with(|| {
+                    queue.push_back(target);
+                    next
+                });
+                dfa.delta.insert((from, sym), to);
+            }
+        }
+
+        dfa
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::states::AcceptedStateLabel;
+    use crate::tokenizer::token_types::Keyword::{Else, If};
+    use crate::tokenizer::token_types::TokenKind::Keyword;
+
+    fn sym(c: char) -> Symbol {
+        Symbol::new(c as u8)
+    }
+
+    /// This DFA recognizes "a" and "b" as `if`, and "c" as `else`. It also has a useless state,
+    /// "d", which can never reach an accepting state.
+    fn redundant_dfa() -> DFA<&'static str, AcceptedStateLabel> {
+        let if_ = AcceptedStateLabel::TokenType { kind: Keyword(If) };
+        let else_ = AcceptedStateLabel::TokenType {
+            kind: Keyword(Else),
+        };
+
+        DFA {
+            init: "init",
+            accepted: vec![("a", if_.clone()), ("b", if_), ("c", else_)]
+                .into_iter()
+                .collect(),
+            delta: vec![
+                (("init", sym('a')), "a"),
+                (("init", sym('b')), "b"),
+                (("init", sym('c')), "c"),
+                (("init", sym('d')), "d"),
+                (("d", sym('d')), "d"),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    #[test]
+    fn merge_equivalent_states() {
+        let dfa = redundant_dfa();
+        assert_eq!(5, dfa.num_states());
+
+        let min = dfa.minimize();
+        assert_eq!(3, min.num_states());
+        assert_eq!(3, min.delta.len());
+
+        min._check(&["a", "b", "c"], &["", "d", "dd", "ab", "ca"]);
+
+        // "a" and "b" merged; "c" didn't, since it has a different label.
+        let target = |c| min.delta[&(min.init, sym(c))];
+        assert_eq!(target('a'), target('b'));
+        assert_ne!(target('a'), target('c'));
+        assert_eq!(min.accepted[&target('a')], dfa.accepted["a"]);
+        assert_eq!(min.accepted[&target('c')], dfa.accepted["c"]);
+    }
+
+    /// Minimizing should be idempotent, and produce the same numbering every time.
+    #[test]
+    fn deterministic() {
+        let min = redundant_dfa().minimize();
+        let again = min.minimize();
+        assert_eq!(min.init, again.init);
+        assert_eq!(min.accepted, again.accepted);
+        assert_eq!(min.delta, again.delta);
+    }
+}
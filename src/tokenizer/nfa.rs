@@ -1,30 +1,77 @@
 use crate::tokenizer::dfa::DFA;
 use crate::tokenizer::nfa_to_dfa::NfaConverter;
-use crate::tokenizer::states::{AcceptedStateLabel, StateSet, Symbol};
-use std::collections::HashMap as Map;
+use crate::tokenizer::states::{StateSet, Symbol};
+use std::collections::{BTreeSet, HashMap as Map};
 use std::hash::Hash;
 
-/// We don't provide any methods to run the NFA; you must convert it to a DFA first via `to_dfa`.
+/// To tokenize with an NFA, convert it to a DFA first via `to_dfa`. `_longest_match` runs the NFA
+/// directly, but it's slow; it's for checking the DFA against.
+///
+/// Each accepted state is labelled with an `L`, e.g. the token type it accepts.
 #[derive(Debug)]
-pub struct NFA<S> {
+#[allow(clippy::upper_case_acronyms)]
+pub struct NFA<S, L> {
     pub init: S,
-    pub accepted: Map<S, AcceptedStateLabel>,
+    pub accepted: Map<S, L>,
     pub delta: Map<(S, Symbol), Vec<S>>,
     pub epsilon: Map<S, Vec<S>>,
 }
 
-impl<S: Copy + Ord + Hash> NFA<S> {
+impl<S: Copy + Ord + Hash, L: Clone> NFA<S, L> {
     /// Convert this NFA into an equivalent DFA (they accept the same strings).
-    pub fn to_dfa(&self) -> DFA<StateSet<S>> {
+    pub fn to_dfa(&self) -> DFA<StateSet<S>, L> {
         NfaConverter::new(self).to_dfa()
     }
+
+    /// Max munch: the longest prefix of `input` that the NFA accepts, as its length in symbols,
+    /// along with its label. `None` if no non-empty prefix is accepted.
+    ///
+    /// This simulates the NFA directly, by tracking the set of states it could be in. Ties are
+    /// broken the same way as in `to_dfa`: the smallest accepted state wins.
+    pub fn _longest_match(&self, input: impl IntoIterator<Item = Symbol>) -> Option<(usize, &L)> {
+        // `states`, plus every state reachable from them via epsilon transitions.
+        let eps_closure = |mut states: BTreeSet<S>| {
+            let mut stack: Vec<_> = states.iter().copied().collect();
+            while let Some(s) = stack.pop() {
+                for &nbr in self.epsilon.get(&s).into_iter().flatten() {
+                    if states.insert(nbr) {
+                        stack.push(nbr);
+                    }
+                }
+            }
+            states
+        };
+
+        let mut states = eps_closure(std::iter::once(self.init).collect());
+        let mut longest = None;
+
+        for (i, sym) in input.into_iter().enumerate() {
+            let next = states
+                .iter()
+                .filter_map(|&s| self.delta.get(&(s, sym)))
+                .flatten()
+                .copied()
+                .collect();
+            states = eps_closure(next);
+            if states.is_empty() {
+                break;
+            }
+
+            // Since the set is sorted, this finds the smallest accepted state.
+            if let Some(label) = states.iter().find_map(|s| self.accepted.get(s)) {
+                longest = Some((i + 1, label));
+            }
+        }
+        longest
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tokenizer::states::AcceptedStateLabel;
     use crate::tokenizer::token_types::Keyword::If;
-    use crate::tokenizer::tokens::Token::Keyword;
+    use crate::tokenizer::token_types::TokenKind::Keyword;
 
     /// Helper struct for specifying small NFAs in unit tests.
     struct NFABuilder<'a> {
@@ -35,7 +82,7 @@ mod tests {
     }
 
     impl<'a> NFABuilder<'a> {
-        fn build(self) -> NFA<&'a str> {
+        fn build(self) -> NFA<&'a str, AcceptedStateLabel> {
             let init = self.init;
             let accepted = self.accepted.into_iter().collect();
             let delta = self
@@ -55,8 +102,8 @@ mod tests {
     }
 
     /// This NFA recognizes the language {"a", "ab", "aba"}.
-    fn simple_nfa() -> NFA<&'static str> {
-        let if_ = AcceptedStateLabel::TokenType { type_: Keyword(If) };
+    fn simple_nfa() -> NFA<&'static str, AcceptedStateLabel> {
+        let if_ = AcceptedStateLabel::TokenType { kind: Keyword(If) };
 
         NFABuilder {
             init: "init",
@@ -77,11 +124,42 @@ mod tests {
         let nfa = simple_nfa();
         let dfa = nfa.to_dfa();
 
-        // It's also worth inspecting this to see that it looks right.
-        dbg!(&nfa, &dfa);
+        // The subsets are {a1, a2} after "a", {ab, a1} after "ab" (via the ε-move), and {aba}
+        // after "aba", all accepting.
+        let write_label = |l: &AcceptedStateLabel| format!("{:?}", l);
+        assert_eq!(5, nfa.dump("", write_label).unwrap().num_states());
+        let expected = "\
+0 (start)
+    a -> 1
+1 accept TokenType { kind: Keyword(If) }
+    b -> 2
+2 accept TokenType { kind: Keyword(If) }
+    a -> 3
+3 accept TokenType { kind: Keyword(If) }
+";
+        assert_eq!(expected, dfa.dump("", write_label).unwrap().to_string());
 
         let accepted = vec!["a", "ab", "aba"];
         let rejected = vec!["", "b", "aa", "ba", "bb", "bba", "aaaaaba"];
         dfa._check(&accepted, &rejected);
     }
+
+    #[test]
+    fn longest_match() {
+        let nfa = simple_nfa();
+        let if_ = AcceptedStateLabel::TokenType { kind: Keyword(If) };
+        for (input, expected) in [
+            ("", None),
+            ("b", None),
+            ("a", Some(1)),
+            ("ab", Some(2)),
+            ("aba", Some(3)),
+            ("abab", Some(3)),
+            ("aaba", Some(1)),
+        ] {
+            let symbols = input.bytes().map(Symbol::new);
+            let expected = expected.map(|len| (len, &if_));
+            assert_eq!(expected, nfa._longest_match(symbols), "{:?}", input);
+        }
+    }
 }
@@ -1,22 +1,22 @@
 use crate::tokenizer::dfa::DFA;
 use crate::tokenizer::nfa::NFA;
-use crate::tokenizer::states::{AcceptedStateLabel, StateSet, Symbol};
+use crate::tokenizer::states::{StateSet, Symbol};
 use std::collections::{BTreeSet, HashMap as Map, HashSet as Set};
 use std::hash::Hash;
 
 /// Helper struct to convert an NFA to an equivalent DFA.
 ///
 /// The generic type `S` will usually be the type `State`, except in unit tests.
-pub struct NfaConverter<'a, S> {
-    nfa: &'a NFA<S>,
+pub struct NfaConverter<'a, S, L> {
+    nfa: &'a NFA<S, L>,
 
     /// An alternative representation of the keys in `nfa.delta`.
     /// This lets us efficiently find the active symbols of a given state of the nfa.
     active_symbols: Map<S, Vec<Symbol>>,
 }
 
-impl<'a, S: Copy + Ord + Hash> NfaConverter<'a, S> {
-    pub fn new(nfa: &'a NFA<S>) -> Self {
+impl<'a, S: Copy + Ord + Hash, L: Clone> NfaConverter<'a, S, L> {
+    pub fn new(nfa: &'a NFA<S, L>) -> Self {
         let mut tmp = Self {
             nfa,
             active_symbols: Map::new(),
@@ -36,7 +36,8 @@ impl<'a, S: Copy + Ord + Hash> NfaConverter<'a, S> {
     /// Perform the conversion. (Sometimes known as the "powerset" construction.)
     ///
     /// The "states" of the resulting DFA have type `StateSet<S>`.
-    pub fn to_dfa(self) -> DFA<StateSet<S>> {
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_dfa(self) -> DFA<StateSet<S>, L> {
         let init = self.eps_closure_one_state(self.nfa.init);
         let mut dfa = DFA {
             init: init.copy(),
@@ -73,14 +74,15 @@ impl<'a, S: Copy + Ord + Hash> NfaConverter<'a, S> {
         dfa
     }
 
-    /// Is `ss` is accepted by the DFA, and if so, which token type does it yield?
+    /// Is `ss` is accepted by the DFA, and if so, what's its label (e.g. which token type does it
+    /// yield)?
     ///
     /// This is determined by whether any of the "inner" states of the NFA are accepted.
     ///
     /// Ties for token type are broken by priority, via `S`'s `Ord` implementation. This means the
     /// most "important" tokens should have the smallest accepting states; e.g. keywords before
     /// identifiers, etc.
-    fn is_accepted(&self, ss: &StateSet<S>) -> Option<AcceptedStateLabel> {
+    fn is_accepted(&self, ss: &StateSet<S>) -> Option<L> {
         // Since `StateSet`s are sorted, we'll find the smallest accepted state.
         for s in ss.states() {
             if let Some(label) = self.nfa.accepted.get(s) {
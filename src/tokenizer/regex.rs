@@ -0,0 +1,379 @@
+//! A small regex language for describing token types, which compiles to NFA fragments via
+//! Thompson's construction.
+//!
+//! The syntax is a subset of the usual:
+//! - `ab` concatenation, `a|b` alternation, `(a)` grouping.
+//! - `a*`, `a+`, `a?` repetition.
+//! - `[abc]`, `[a-z]` char classes, and `[^abc]` negated classes. A negated class includes
+//!   `Symbol::NON_ASCII`, so e.g. `[^\n]` matches any char except a newline.
+//! - `.` matches any char except a newline.
+//! - `\n`, `\t`, `\r` and `\f` escapes. Any other punctuation can be escaped to match it literally,
+//!   e.g. `\*` or `\\`.
+//!
+//! Patterns must be ASCII.
+
+use crate::tokenizer::nfa::NFA;
+use crate::tokenizer::states::{State, Symbol};
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Regex {
+    /// Matches the empty string.
+    Empty,
+    /// Matches any one of these symbols. Sorted by `Symbol::index`, without duplicates.
+    Symbols(Vec<Symbol>),
+    Concat(Vec<Regex>),
+    Alt(Vec<Regex>),
+    Star(Box<Regex>),
+    Plus(Box<Regex>),
+    Optional(Box<Regex>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegexError {
+    /// The pattern ended in the middle of something, e.g. `(ab` or `[ab`.
+    UnexpectedEnd,
+    /// A char that doesn't make sense here, e.g. the `)` in `a)`. `pos` is a byte offset.
+    Unexpected {
+        pos: usize,
+        c: char,
+    },
+    NonAscii {
+        pos: usize,
+        c: char,
+    },
+    /// A range like `[z-a]`.
+    BadRange {
+        pos: usize,
+    },
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegexError::UnexpectedEnd => write!(f, "unexpected end of pattern"),
+            RegexError::Unexpected { pos, c } => {
+                write!(f, "unexpected {:?} at offset {}", c, pos)
+            }
+            RegexError::NonAscii { pos, c } => {
+                write!(f, "non-ASCII char {:?} at offset {}", c, pos)
+            }
+            RegexError::BadRange { pos } => write!(f, "backwards range at offset {}", pos),
+        }
+    }
+}
+
+impl Regex {
+    pub fn parse(pattern: &str) -> Result<Self, RegexError> {
+        let mut parser = Parser {
+            chars: pattern.char_indices().peekable(),
+        };
+        let regex = parser.alt()?;
+        match parser.chars.next() {
+            None => Ok(regex),
+            Some((pos, c)) => Err(RegexError::Unexpected { pos, c }),
+        }
+    }
+
+    /// Does this regex match the empty string?
+    pub fn matches_empty(&self) -> bool {
+        match self {
+            Regex::Empty | Regex::Star(_) | Regex::Optional(_) => true,
+            Regex::Symbols(_) => false,
+            Regex::Concat(parts) => parts.iter().all(Regex::matches_empty),
+            Regex::Alt(options) => options.iter().any(Regex::matches_empty),
+            Regex::Plus(inner) => inner.matches_empty(),
+        }
+    }
+
+    /// Add states and transitions to `nfa` for recognizing this regex, using Thompson's
+    /// construction. New states come from `new_state`.
+    ///
+    /// Returns the start and end states of the fragment. Nothing links to the start yet, and the
+    /// end isn't accepted yet; that's up to the caller.
+    pub fn add_to_nfa<L>(
+        &self,
+        nfa: &mut NFA<State, L>,
+        new_state: &mut dyn FnMut() -> State,
+    ) -> (State, State) {
+        match self {
+            Regex::Empty => {
+                let start = new_state();
+                let end = new_state();
+                eps(nfa, start, end);
+                (start, end)
+            }
+            Regex::Symbols(symbols) => {
+                let start = new_state();
+                let end = new_state();
+                for &sym in symbols {
+                    nfa.delta.entry((start, sym)).or_default().push(end);
+                }
+                (start, end)
+            }
+            Regex::Concat(parts) => {
+                let mut fragments = parts.iter().map(|r| r.add_to_nfa(nfa, new_state));
+                let (start, mut end) = match fragments.next() {
+                    Some(first) => first,
+                    None => return Regex::Empty.add_to_nfa(nfa, new_state),
+                };
+
+                // (Collect first, since `fragments` borrows `nfa`.)
+                let rest: Vec<_> = fragments.collect();
+                for (next_start, next_end) in rest {
+                    eps(nfa, end, next_start);
+                    end = next_end;
+                }
+                (start, end)
+            }
+            Regex::Alt(options) => {
+                let start = new_state();
+                let fragments: Vec<_> = options
+                    .iter()
+                    .map(|r| r.add_to_nfa(nfa, new_state))
+                    .collect();
+                let end = new_state();
+                for (inner_start, inner_end) in fragments {
+                    eps(nfa, start, inner_start);
+                    eps(nfa, inner_end, end);
+                }
+                (start, end)
+            }
+            Regex::Star(inner) | Regex::Plus(inner) | Regex::Optional(inner) => {
+                let start = new_state();
+                let (inner_start, inner_end) = inner.add_to_nfa(nfa, new_state);
+                let end = new_state();
+
+                eps(nfa, start, inner_start);
+                eps(nfa, inner_end, end);
+                if !matches!(self, Regex::Plus(_)) {
+                    // Skip it.
+                    eps(nfa, start, end);
+                }
+                if !matches!(self, Regex::Optional(_)) {
+                    // Repeat it.
+                    eps(nfa, inner_end, inner_start);
+                }
+                (start, end)
+            }
+        }
+    }
+}
+
+/// Add an epsilon transition.
+fn eps<L>(nfa: &mut NFA<State, L>, src: State, dest: State) {
+    nfa.epsilon.entry(src).or_default().push(dest);
+}
+
+/// A recursive descent parser for regexes.
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn alt(&mut self) -> Result<Regex, RegexError> {
+        let mut options = vec![self.concat()?];
+        while self.chars.next_if(|&(_, c)| c == '|').is_some() {
+            options.push(self.concat()?);
+        }
+
+        Ok(if options.len() == 1 {
+            options.pop().unwrap()
+        } else {
+            Regex::Alt(options)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Regex, RegexError> {
+        let mut parts = vec![];
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            parts.push(self.repeat()?);
+        }
+
+        Ok(match parts.len() {
+            0 => Regex::Empty,
+            1 => parts.pop().unwrap(),
+            _ => Regex::Concat(parts),
+        })
+    }
+
+    fn repeat(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = self.atom()?;
+        while let Some((_, c)) = self.chars.next_if(|&(_, c)| matches!(c, '*' | '+' | '?')) {
+            let inner = Box::new(regex);
+            regex = match c {
+                '*' => Regex::Star(inner),
+                '+' => Regex::Plus(inner),
+                _ => Regex::Optional(inner),
+            };
+        }
+        Ok(regex)
+    }
+
+    fn atom(&mut self) -> Result<Regex, RegexError> {
+        let (pos, c) = self.next()?;
+        match c {
+            '(' => {
+                let regex = self.alt()?;
+                match self.next()? {
+                    (_, ')') => Ok(regex),
+                    (pos, c) => Err(RegexError::Unexpected { pos, c }),
+                }
+            }
+            '[' => self.class(),
+            '.' => Ok(Regex::Symbols(
+                Symbol::all().filter(|s| s.to_char() != '\n').collect(),
+            )),
+            '\\' => Ok(Regex::Symbols(vec![self.escape()?])),
+            '*' | '+' | '?' | ')' | ']' => Err(RegexError::Unexpected { pos, c }),
+            c => Ok(Regex::Symbols(vec![Symbol::new(c as u8)])),
+        }
+    }
+
+    /// Parse a char class, after the opening `[`.
+    fn class(&mut self) -> Result<Regex, RegexError> {
+        let negated = self.chars.next_if(|&(_, c)| c == '^').is_some();
+        let mut members = [false; Symbol::COUNT];
+
+        loop {
+            let (pos, c) = self.next()?;
+            let first = match c {
+                ']' => break,
+                '\\' => self.escape()?,
+                c => Symbol::new(c as u8),
+            };
+
+            // A range, unless the `-` is right before the closing `]`.
+            let is_range = {
+                let mut lookahead = self.chars.clone();
+                matches!(lookahead.next(), Some((_, '-')))
+                    && !matches!(lookahead.next(), Some((_, ']')) | None)
+            };
+            if !is_range {
+                members[first.index()] = true;
+                continue;
+            }
+
+            self.chars.next();
+            let last = match self.next()? {
+                (_, '\\') => self.escape()?,
+                (_, c) => Symbol::new(c as u8),
+            };
+            if last.index() < first.index() {
+                return Err(RegexError::BadRange { pos });
+            }
+            for m in &mut members[first.index()..=last.index()] {
+                *m = true;
+            }
+        }
+
+        Ok(Regex::Symbols(
+            Symbol::all()
+                .filter(|s| members[s.index()] != negated)
+                .collect(),
+        ))
+    }
+
+    /// Parse an escape sequence, after the backslash.
+    fn escape(&mut self) -> Result<Symbol, RegexError> {
+        let (pos, c) = self.next()?;
+        let c = match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'f' => '\x0C',
+            c if c.is_ascii_punctuation() => c,
+            c => return Err(RegexError::Unexpected { pos, c }),
+        };
+        Ok(Symbol::new(c as u8))
+    }
+
+    /// The next char, which must be ASCII.
+    fn next(&mut self) -> Result<(usize, char), RegexError> {
+        match self.chars.next() {
+            None => Err(RegexError::UnexpectedEnd),
+            Some((pos, c)) if !c.is_ascii() => Err(RegexError::NonAscii { pos, c }),
+            Some(next) => Ok(next),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap as Map;
+
+    /// Compile a regex to a DFA, via an NFA.
+    fn check(pattern: &str, accepted: &[&str], rejected: &[&str]) {
+        let regex = Regex::parse(pattern).unwrap();
+
+        let mut num_states = 0;
+        let mut new_state = || {
+            num_states += 1;
+            State(num_states - 1)
+        };
+        let mut nfa: NFA<State, ()> = NFA {
+            init: State(0),
+            accepted: Map::new(),
+            delta: Map::new(),
+            epsilon: Map::new(),
+        };
+        let (start, end) = regex.add_to_nfa(&mut nfa, &mut new_state);
+        nfa.init = start;
+        nfa.accepted.insert(end, ());
+
+        nfa.to_dfa()._check(accepted, rejected);
+    }
+
+    #[test]
+    fn matching() {
+        check("abc", &["abc"], &["", "ab", "abcc"]);
+        check("a|bc|", &["", "a", "bc"], &["b", "abc"]);
+        check("(ab)*c", &["c", "abc", "ababc"], &["", "ab", "aabc"]);
+        check("a+b?", &["a", "aa", "aab"], &["", "b", "abb"]);
+        check("[a-c_]+", &["a", "cab_"], &["", "d", "-"]);
+        check("[^a-c\\n]", &["d", "-", "\t"], &["a", "\n", "dd"]);
+        check("[-a-]", &["-", "a"], &["b"]);
+        check(".\\*\\.", &["a*.", "**."], &["\n*.", "a*a"]);
+        check(
+            "/\\*([^*]|\\*+[^*/])*\\*+/",
+            &["/**/", "/* a **b* */"],
+            &["/*/", "/* */ */"],
+        );
+    }
+
+    #[test]
+    fn matches_empty() {
+        for (pattern, expected) in [
+            ("a", false),
+            ("a*", true),
+            ("(a?b?)+", true),
+            ("a|", true),
+            ("a?b", false),
+            ("[^a]", false),
+        ] {
+            let regex = Regex::parse(pattern).unwrap();
+            assert_eq!(expected, regex.matches_empty(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        for (pattern, expected) in [
+            ("(ab", RegexError::UnexpectedEnd),
+            ("[ab", RegexError::UnexpectedEnd),
+            ("ab)", RegexError::Unexpected { pos: 2, c: ')' }),
+            ("*a", RegexError::Unexpected { pos: 0, c: '*' }),
+            ("\\q", RegexError::Unexpected { pos: 1, c: 'q' }),
+            ("é", RegexError::NonAscii { pos: 0, c: 'é' }),
+            ("[z-a]", RegexError::BadRange { pos: 1 }),
+        ] {
+            assert_eq!(Err(expected), Regex::parse(pattern), "{}", pattern);
+        }
+    }
+}
@@ -1,42 +1,74 @@
-use crate::tokenizer::tokens::Token;
+use crate::tokenizer::token_types::TokenKind::{self, *};
+use crate::tokenizer::token_types::{KEYWORDS, OPERATORS, SEPARATORS};
 use std::fmt;
 use std::iter::FromIterator;
 use std::rc::Rc;
 
 /// A symbol in the input stream.
 ///
-/// Used to label state transitions in DFAs and NFAs.
+/// Used to label state transitions in DFAs and NFAs. Every ASCII character is its own symbol, and
+/// all non-ASCII characters share the special symbol `NON_ASCII`. (Joos only allows these in
+/// comments and literals, so we never need to tell them apart.)
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Symbol {
-    ascii_byte: u8,
+    /// An ASCII byte, or 128 for `NON_ASCII`.
+    byte: u8,
 }
 
 impl Symbol {
+    /// Stands in for any non-ASCII character.
+    pub const NON_ASCII: Symbol = Symbol { byte: 128 };
+
+    /// The number of distinct symbols: one per ASCII character, plus `NON_ASCII`.
+    pub const COUNT: usize = 129;
+
     pub fn new(ascii_byte: u8) -> Self {
         assert!(ascii_byte < 128);
-        Self { ascii_byte }
+        Self { byte: ascii_byte }
+    }
+
+    /// The symbol for a given character.
+    pub fn from_char(c: char) -> Self {
+        if c.is_ascii() {
+            Self::new(c as u8)
+        } else {
+            Self::NON_ASCII
+        }
+    }
+
+    /// Every symbol, in order of `index`. Includes `NON_ASCII`.
+    pub fn all() -> impl Iterator<Item = Symbol> {
+        (0..Self::COUNT as u8).map(|byte| Self { byte })
+    }
+
+    /// A unique number for this symbol, less than `Symbol::COUNT`. Handy for indexing tables.
+    pub fn index(self) -> usize {
+        self.byte as usize
     }
 
+    /// Convert back to an ASCII char. `NON_ASCII` is converted to U+0080, which is just filler.
     pub fn to_char(self) -> char {
-        self.ascii_byte as char
+        self.byte as char
     }
 }
 
 impl fmt::Debug for Symbol {
     /// We could just derive, but this avoids newlines in {:#?} output.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Symbol({:?})", self.to_char())
+        if *self == Self::NON_ASCII {
+            write!(f, "Symbol(NON_ASCII)")
+        } else {
+            write!(f, "Symbol({:?})", self.to_char())
+        }
     }
 }
 
-/// Used in NFAs and DFAs to label each accepted state with the token type it accepts.
+/// The Joos token type matched by a rule of the Joos lexer spec (see `lexer`).
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AcceptedStateLabel {
-    /// Beware that the inner data of the `Token` is likely just filler. E.g., it will never
-    /// contain an actual identifier from the input stream. So you'll have to fill it in during
-    /// tokenizing.
+    /// The data of the token (e.g. the name of an identifier) is filled in during tokenizing.
     TokenType {
-        type_: Token<'static>,
+        kind: TokenKind,
     },
     LineComment,
     StarComment,
@@ -44,6 +76,43 @@ pub enum AcceptedStateLabel {
     Whitespace,
 }
 
+impl AcceptedStateLabel {
+    /// The label for a rule of the Joos lexer spec, given its name. Keywords, separators, operators,
+    /// and the `true`, `false` and `null` literals are named by their text.
+    pub fn from_rule_name(name: &str) -> Option<Self> {
+        let kind = match name {
+            "LINE_COMMENT" => return Some(AcceptedStateLabel::LineComment),
+            "STAR_COMMENT" => return Some(AcceptedStateLabel::StarComment),
+            "JAVADOC_COMMENT" => return Some(AcceptedStateLabel::JavadocComment),
+            "WHITESPACE" => return Some(AcceptedStateLabel::Whitespace),
+
+            "IDENTIFIER" => Identifier,
+            "INT_LITERAL" => IntLiteral,
+            "CHAR_LITERAL" => CharLiteral,
+            "STRING_LITERAL" => StringLiteral,
+            "true" | "false" => BoolLiteral,
+            "null" => NullLiteral,
+            "HEX_LITERAL" => HexIntLiteral,
+            "OCTAL_LITERAL" => OctalIntLiteral,
+            "LONG_LITERAL" => LongLiteral,
+            "FLOAT_LITERAL" => FloatLiteral,
+            "DOUBLE_LITERAL" => DoubleLiteral,
+
+            _ => {
+                let is_named = |t: &dyn fmt::Display| t.to_string() == name;
+                if let Some(&k) = KEYWORDS.iter().find(|k| is_named(*k)) {
+                    Keyword(k)
+                } else if let Some(&s) = SEPARATORS.iter().find(|s| is_named(*s)) {
+                    Separator(s)
+                } else {
+                    Operator(*OPERATORS.iter().find(|o| is_named(*o))?)
+                }
+            }
+        };
+        Some(AcceptedStateLabel::TokenType { kind })
+    }
+}
+
 /// A state of a DFA or NFA.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct State(pub u32);
@@ -1,6 +1,8 @@
+use std::collections::HashMap as Map;
 use std::fmt;
+use std::sync::OnceLock;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Keyword {
     Abstract,
     Boolean,
@@ -54,9 +56,22 @@ pub enum Keyword {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Literal {
-    /// Anywhere in 0..=2^31, but 2^31 should fail to parse unless it is preceeded by a unary
-    /// negation operator.
-    Int(u32),
+    /// The digits, with unicode escapes translated. These aren't range-checked when tokenizing,
+    /// since 2^31 is only valid directly after unary minus; see `int_literals`.
+    Int(String),
+    /// Java only: a hex int literal like `0x1F`. As written, with unicode escapes translated.
+    HexInt(String),
+    /// Java only: an octal int literal like `017`. As written, with unicode escapes translated.
+    OctalInt(String),
+    /// Java only: a long literal like `10L` or `0x1FL`. As written, with unicode escapes translated,
+    /// but without the `L`.
+    Long(String),
+    /// Java only: a float literal like `1.5e3f`. As written, with unicode escapes translated, but
+    /// without the `f`.
+    Float(String),
+    /// Java only: a double literal like `.5` or `2d`. As written, with unicode escapes translated,
+    /// but without any `d`.
+    Double(String),
     Bool(bool),
     /// (If this was an escaped character, it has been resolved.)
     Char(char),
@@ -65,7 +80,7 @@ pub enum Literal {
     Null,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Separator {
     LParen,
     RParen,
@@ -78,7 +93,7 @@ pub enum Separator {
     Dot,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Operator {
     Assign,
     Gt,
@@ -119,6 +134,226 @@ pub enum Operator {
     URShiftEq,
 }
 
+/// The kind of a token, without any of its data. One per terminal of the grammar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TokenKind {
+    Identifier,
+    Keyword(Keyword),
+    Separator(Separator),
+    Operator(Operator),
+    IntLiteral,
+    HexIntLiteral,
+    OctalIntLiteral,
+    LongLiteral,
+    FloatLiteral,
+    DoubleLiteral,
+    BoolLiteral,
+    CharLiteral,
+    StringLiteral,
+    NullLiteral,
+}
+
+impl TokenKind {
+    const LITERALS: [TokenKind; 10] = {
+        use TokenKind::*;
+
+        [
+            IntLiteral,
+            HexIntLiteral,
+            OctalIntLiteral,
+            LongLiteral,
+            FloatLiteral,
+            DoubleLiteral,
+            BoolLiteral,
+            CharLiteral,
+            StringLiteral,
+            NullLiteral,
+        ]
+    };
+
+    /// Every kind, in order.
+    pub fn all() -> impl Iterator<Item = TokenKind> {
+        let keywords = KEYWORDS.iter().map(|&k| TokenKind::Keyword(k));
+        let separators = SEPARATORS.iter().map(|&s| TokenKind::Separator(s));
+        let operators = OPERATORS.iter().map(|&o| TokenKind::Operator(o));
+        std::iter::once(TokenKind::Identifier)
+            .chain(keywords)
+            .chain(separators)
+            .chain(operators)
+            .chain(Self::LITERALS.iter().copied())
+    }
+
+    /// The kind with the given name (see `name`), if any.
+    pub fn from_name(name: &str) -> Option<TokenKind> {
+        // This is called for every terminal in a grammar or parse table, so build the lookup once.
+        static BY_NAME: OnceLock<Map<&'static str, TokenKind>> = OnceLock::new();
+        let by_name = BY_NAME.get_or_init(|| Self::all().map(|k| (k.name(), k)).collect());
+        by_name.get(name).copied()
+    }
+
+    /// The name of this kind as a terminal in grammar files, e.g. `ID`, `WHILE`, `LPAREN` or
+    /// `INTEGER_LITERAL`. These are stable, so don't rename them.
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenKind::Identifier => "ID",
+            TokenKind::Keyword(k) => k.name(),
+            TokenKind::Separator(s) => s.name(),
+            TokenKind::Operator(o) => o.name(),
+            TokenKind::IntLiteral => "INTEGER_LITERAL",
+            TokenKind::HexIntLiteral => "HEX_LITERAL",
+            TokenKind::OctalIntLiteral => "OCTAL_LITERAL",
+            TokenKind::LongLiteral => "LONG_LITERAL",
+            TokenKind::FloatLiteral => "FLOAT_LITERAL",
+            TokenKind::DoubleLiteral => "DOUBLE_LITERAL",
+            TokenKind::BoolLiteral => "BOOLEAN_LITERAL",
+            TokenKind::CharLiteral => "CHAR_LITERAL",
+            TokenKind::StringLiteral => "STRING_LITERAL",
+            TokenKind::NullLiteral => "NULL_LITERAL",
+        }
+    }
+}
+
+impl Literal {
+    pub fn kind(&self) -> TokenKind {
+        use Literal::*;
+
+        match self {
+            Int(_) => TokenKind::IntLiteral,
+            HexInt(_) => TokenKind::HexIntLiteral,
+            OctalInt(_) => TokenKind::OctalIntLiteral,
+            Long(_) => TokenKind::LongLiteral,
+            Float(_) => TokenKind::FloatLiteral,
+            Double(_) => TokenKind::DoubleLiteral,
+            Bool(_) => TokenKind::BoolLiteral,
+            Char(_) => TokenKind::CharLiteral,
+            StringLit(_) => TokenKind::StringLiteral,
+            Null => TokenKind::NullLiteral,
+        }
+    }
+}
+
+impl Keyword {
+    /// The keyword in upper case, e.g. `INSTANCEOF`.
+    fn name(self) -> &'static str {
+        use Keyword::*;
+
+        match self {
+            Abstract => "ABSTRACT",
+            Boolean => "BOOLEAN",
+            Break => "BREAK",
+            Byte => "BYTE",
+            Case => "CASE",
+            Catch => "CATCH",
+            Char => "CHAR",
+            Class => "CLASS",
+            Const => "CONST",
+            Continue => "CONTINUE",
+            Default => "DEFAULT",
+            Do => "DO",
+            Double => "DOUBLE",
+            Else => "ELSE",
+            Extends => "EXTENDS",
+            Final => "FINAL",
+            Finally => "FINALLY",
+            Float => "FLOAT",
+            For => "FOR",
+            Goto => "GOTO",
+            If => "IF",
+            Implements => "IMPLEMENTS",
+            Import => "IMPORT",
+            Instanceof => "INSTANCEOF",
+            Int => "INT",
+            Interface => "INTERFACE",
+            Long => "LONG",
+            Native => "NATIVE",
+            New => "NEW",
+            Package => "PACKAGE",
+            Private => "PRIVATE",
+            Protected => "PROTECTED",
+            Public => "PUBLIC",
+            Return => "RETURN",
+            Short => "SHORT",
+            Static => "STATIC",
+            Strictfp => "STRICTFP",
+            Super => "SUPER",
+            Switch => "SWITCH",
+            Synchronized => "SYNCHRONIZED",
+            This => "THIS",
+            Throw => "THROW",
+            Throws => "THROWS",
+            Transient => "TRANSIENT",
+            Try => "TRY",
+            Void => "VOID",
+            Volatile => "VOLATILE",
+            While => "WHILE",
+        }
+    }
+}
+
+impl Separator {
+    fn name(self) -> &'static str {
+        use Separator::*;
+
+        match self {
+            LParen => "LPAREN",
+            RParen => "RPAREN",
+            LBrace => "LBRACE",
+            RBrace => "RBRACE",
+            LBracket => "LBRACKET",
+            RBracket => "RBRACKET",
+            Semicolon => "SEMICOLON",
+            Comma => "COMMA",
+            Dot => "DOT",
+        }
+    }
+}
+
+impl Operator {
+    fn name(self) -> &'static str {
+        use Operator::*;
+
+        match self {
+            Assign => "ASSIGN",
+            Gt => "GT",
+            Lt => "LT",
+            Not => "NOT",
+            BitNot => "BIT_NOT",
+            Question => "QUESTION",
+            Colon => "COLON",
+            Eq => "EQ",
+            Le => "LE",
+            Ge => "GE",
+            Ne => "NE",
+            And => "AND",
+            Or => "OR",
+            Increment => "INCREMENT",
+            Decrement => "DECREMENT",
+            Plus => "PLUS",
+            Minus => "MINUS",
+            Star => "STAR",
+            Divide => "DIVIDE",
+            BitAnd => "BIT_AND",
+            BitOr => "BIT_OR",
+            BirXor => "BIT_XOR",
+            Mod => "MOD",
+            LShift => "LSHIFT",
+            RShift => "RSHIFT",
+            URShift => "URSHIFT",
+            PlusEq => "PLUS_EQ",
+            MinusEq => "MINUS_EQ",
+            TimesEq => "TIMES_EQ",
+            DivideEq => "DIVIDE_EQ",
+            BitAndEq => "BIT_AND_EQ",
+            BitOrEq => "BIT_OR_EQ",
+            BitXorEq => "BIT_XOR_EQ",
+            ModEq => "MOD_EQ",
+            LShiftEq => "LSHIFT_EQ",
+            RShiftEq => "RSHIFT_EQ",
+            URShiftEq => "URSHIFT_EQ",
+        }
+    }
+}
+
 pub const KEYWORDS: [Keyword; 48] = {
     use Keyword::*;
 
@@ -319,3 +554,39 @@ impl fmt::Display for Operator {
         write!(f, "{}", s)
     }
 }
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet as Set;
+
+    #[test]
+    fn names() {
+        let kinds: Vec<_> = TokenKind::all().collect();
+        assert_eq!(1 + 48 + 9 + 37 + 10, kinds.len());
+
+        let names: Set<_> = kinds.iter().map(|k| k.name()).collect();
+        assert_eq!(kinds.len(), names.len(), "names aren't unique");
+
+        for kind in kinds {
+            assert_eq!(Some(kind), TokenKind::from_name(kind.name()));
+        }
+
+        for (name, kind) in [
+            ("ID", TokenKind::Identifier),
+            ("INTEGER_LITERAL", TokenKind::IntLiteral),
+            ("LPAREN", TokenKind::Separator(Separator::LParen)),
+            ("INSTANCEOF", TokenKind::Keyword(Keyword::Instanceof)),
+            ("URSHIFT_EQ", TokenKind::Operator(Operator::URShiftEq)),
+        ] {
+            assert_eq!(Some(kind), TokenKind::from_name(name));
+        }
+        assert_eq!(None, TokenKind::from_name("if"));
+    }
+}
@@ -1,16 +1,33 @@
-use crate::tokenizer::token_types::{Keyword, Literal, Operator, Separator};
-use crate::tokenizer::Position;
+use crate::diagnostics::Diagnostic;
+use crate::source_map::Span;
+use crate::tokenizer::token_types::{Keyword, Literal, Operator, Separator, TokenKind};
+use crate::tokenizer::{Dialect, Position};
+use std::borrow::Cow;
+use std::fmt;
 
 /// Diffent types of tokens in the language.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token<'a> {
-    Identifier(&'a str),
+    /// The name, with unicode escapes translated. Borrowed from the input if there weren't any.
+    Identifier(Cow<'a, str>),
     Keyword(Keyword),
     Literal(Literal),
     Separator(Separator),
     Operator(Operator),
 }
 
+impl Token<'_> {
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::Identifier(_) => TokenKind::Identifier,
+            Token::Keyword(k) => TokenKind::Keyword(*k),
+            Token::Literal(l) => l.kind(),
+            Token::Separator(s) => TokenKind::Separator(*s),
+            Token::Operator(o) => TokenKind::Operator(*o),
+        }
+    }
+}
+
 /// A token in the output stream of the tokenizer, together with some metadata about where it is in
 /// the input stream.
 ///
@@ -20,15 +37,26 @@ pub struct TokenInfo<'a> {
     pub val: Token<'a>,
     pub start: Position<'a>,
     pub lexeme: &'a str,
+    /// The Javadoc comment right before this token, if any. Since Javadoc has to come right
+    /// before a declaration, this is how a declaration finds its documentation.
+    ///
+    /// If there are several, this is the last one. Other comments in between are ignored.
+    pub doc: Option<Box<Comment<'a>>>,
 }
 
 impl TokenInfo<'_> {
     /// Zero-indexed, exclusive.
     pub fn end_col(&self) -> usize {
-        // Relies on the token being single-line and ASCII-only.
+        // Relies on the token being single-line.
         // (This is true of all tokens in our language, so we're good.)
         self.start.col + self.lexeme.len()
     }
+
+    /// The token's location in its source file.
+    pub fn span(&self) -> Span {
+        let lo = self.start.offset();
+        Span::new(self.start.file, lo, lo + self.lexeme.len())
+    }
 }
 
 /// The tokenizer also supports producing an output stream with comments included.
@@ -37,37 +65,307 @@ impl TokenInfo<'_> {
 #[derive(Debug, Clone)]
 pub enum TokenOrComment<'a> {
     Token(TokenInfo<'a>),
-    LineComment {
-        start: Position<'a>,
-    },
-    StarComment {
-        start: Position<'a>,
-        /// Inclusive!
-        end_inclusive: Position<'a>,
-    },
+    /// `// ...`
+    LineComment(Comment<'a>),
+    /// `/* ... */`
+    BlockComment(Comment<'a>),
+    /// `/** ... */`, i.e. Javadoc.
+    DocComment(Comment<'a>),
 }
 
 impl<'a> TokenOrComment<'a> {
     pub fn start(&self) -> Position<'a> {
+        match self {
+            TokenOrComment::Token(t) => t.start,
+            _ => self.comment().unwrap().start,
+        }
+    }
+
+    /// The element's location in its source file.
+    ///
+    /// Line comments extend up to (but not including) the end of the line.
+    pub fn span(&self) -> Span {
+        match self {
+            TokenOrComment::Token(t) => t.span(),
+            _ => self.comment().unwrap().span(),
+        }
+    }
+
+    /// `None` if this is a token.
+    pub fn comment(&self) -> Option<&Comment<'a>> {
         use TokenOrComment::*;
         match self {
-            Token(t) => t.start,
-            LineComment { start } => *start,
-            StarComment { start, .. } => *start,
+            Token(_) => None,
+            LineComment(c) | BlockComment(c) | DocComment(c) => Some(c),
+        }
+    }
+}
+
+/// A comment of any kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment<'a> {
+    pub start: Position<'a>,
+    /// Inclusive! For a line comment, this is the "newline" position at the end of the line.
+    pub end_inclusive: Position<'a>,
+    /// The full comment, including the `//`, or the `/*` and `*/`. Line terminators in a
+    /// multi-line comment are all written as `\n`. Unicode escapes aren't translated.
+    pub text: Cow<'a, str>,
+}
+
+impl Comment<'_> {
+    /// The comment's location in its source file.
+    pub fn span(&self) -> Span {
+        self.start.span_to(self.end_inclusive)
+    }
+
+    /// The text between the comment delimiters, e.g. ` hi ` for `/** hi */`.
+    pub fn body(&self) -> &str {
+        let text = &*self.text;
+        if let Some(body) = text.strip_prefix("//") {
+            return body;
+        }
+
+        // Careful with `/**/`, which is a block comment, not an unclosed doc comment.
+        let body = &text[2..text.len() - 2];
+        match body.strip_prefix('*') {
+            Some(doc) if text.len() >= 5 => doc,
+            _ => body,
         }
     }
 }
 
 /// An error encountered while tokenizing.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TokenError<'a> {
-    /// The input contained a non-ascii character. We currently don't support these.
+    /// The input contained a non-ascii character. These are only allowed in comments and string
+    /// or char literals.
     NonAsciiChar { c: char, pos: Position<'a> },
     /// Not a token, nor a prefix of a token.
     NotAToken {
         start: Position<'a>,
-        /// Exclusive.
+        /// Inclusive. This is the symbol on which the scanner got stuck.
         end: Position<'a>,
     },
     /// A star-comment that is never closed. (The input stream ended first.)
-    UnclosedComment { start: Position<'a> },
+    UnclosedComment {
+        start: Position<'a>,
+        /// The last position in the input stream.
+        end: Position<'a>,
+    },
+    /// An int literal that doesn't fit in a 32-bit signed int, even when negated.
+    IntTooLarge {
+        start: Position<'a>,
+        lexeme: &'a str,
+    },
+    /// `2147483648` (2^31), other than as the operand of unary minus.
+    MinIntNotNegated {
+        start: Position<'a>,
+        lexeme: &'a str,
+    },
+    /// A char literal with nothing between the quotes: `''`.
+    EmptyCharLiteral { start: Position<'a> },
+    /// A char literal with more than one char between the quotes, e.g. `'ab'`.
+    CharLiteralTooLong {
+        start: Position<'a>,
+        lexeme: &'a str,
+    },
+    /// A Java token that isn't part of the dialect being compiled, e.g. `>>=` in Joos. Only
+    /// reported by `Tokenizer::check_supported`.
+    NotInDialect {
+        start: Position<'a>,
+        lexeme: &'a str,
+        dialect: Dialect,
+    },
+    /// A backslash and `u`, not followed by four hex digits, e.g. `\u00G1`. This is an error
+    /// anywhere in the file, even in a comment.
+    BadUnicodeEscape {
+        start: Position<'a>,
+        /// Up to and including the first character that isn't a hex digit, unless that's whitespace
+        /// or the end of the line.
+        lexeme: &'a str,
+    },
+    /// An invalid escape sequence in a string or char literal.
+    BadEscape {
+        /// The position of the backslash.
+        pos: Position<'a>,
+        error: EscapeError,
+    },
+}
+
+impl<'a> TokenError<'a> {
+    /// Where the error starts in the input stream.
+    pub fn start(&self) -> Position<'a> {
+        use TokenError::*;
+        match self {
+            NonAsciiChar { pos, .. } | BadEscape { pos, .. } => *pos,
+            NotAToken { start, .. }
+            | UnclosedComment { start, .. }
+            | IntTooLarge { start, .. }
+            | MinIntNotNegated { start, .. }
+            | NotInDialect { start, .. }
+            | BadUnicodeEscape { start, .. }
+            | EmptyCharLiteral { start }
+            | CharLiteralTooLong { start, .. } => *start,
+        }
+    }
+
+    /// Describe the error to the user, pointing at the offending input.
+    pub fn diagnostic(&self) -> Diagnostic<'a> {
+        use TokenError::*;
+
+        let message = self.to_string();
+        match self {
+            NonAsciiChar { pos, .. } => Diagnostic::error(message, *pos, *pos)
+                .with_note("non-ASCII characters are only allowed in comments and literals"),
+            NotAToken { start, end } => {
+                Diagnostic::error(message, *start, *end).with_label("no token starts like this")
+            }
+            UnclosedComment { start, end } => Diagnostic::error(message, *start, *end)
+                .with_label("comment runs to the end of the file"),
+            IntTooLarge { start, lexeme } => Diagnostic::error(
+                message,
+                *start,
+                last_col(*start, lexeme),
+            )
+            .with_note(
+                "the largest int literal is 2147483647, or 2147483648 directly after unary minus",
+            ),
+            MinIntNotNegated { start, lexeme } => {
+                Diagnostic::error(message, *start, last_col(*start, lexeme))
+                    .with_label("this is one more than the largest int")
+                    .with_note("2147483648 is only allowed as the operand of unary minus")
+            }
+            NotInDialect { start, lexeme, .. } => {
+                Diagnostic::error(message, *start, last_col(*start, lexeme))
+                    .with_label("only in Java")
+            }
+            EmptyCharLiteral { start } => {
+                Diagnostic::error(message, *start, last_col(*start, "''"))
+                    .with_help("to write a single quote, use '\\''")
+            }
+            CharLiteralTooLong { start, lexeme } => {
+                Diagnostic::error(message, *start, last_col(*start, lexeme))
+                    .with_help("string literals use double quotes")
+            }
+            BadUnicodeEscape { start, lexeme } => {
+                Diagnostic::error(message, *start, last_col(*start, lexeme))
+                    .with_note("`\\u` has to be followed by four hex digits, even in a comment")
+            }
+            BadEscape { pos, error } => Diagnostic::error(
+                message,
+                *pos,
+                last_col(*pos, &error.escape_seq()),
+            )
+            .with_help(
+                "valid escapes are \\b \\t \\n \\f \\r \\\" \\' \\\\ and octal escapes like \\0",
+            ),
+        }
+    }
+}
+
+/// The position of the last character of `text`, which starts at `start`.
+fn last_col<'a>(start: Position<'a>, text: &str) -> Position<'a> {
+    let last = text.char_indices().last().map_or(0, |(i, _)| i);
+    Position {
+        col: start.col + last,
+        ..start
+    }
+}
+
+impl fmt::Display for TokenError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use TokenError::*;
+        match self {
+            NonAsciiChar { c, .. } => write!(f, "non-ascii character {:?}", c),
+            NotAToken { start, end } => {
+                let text = if start.line_num == end.line_num {
+                    &start.line[start.col..end.col]
+                } else {
+                    &start.line[start.col..]
+                };
+                let stuck = end.source_char();
+                if text.is_empty() {
+                    write!(f, "not a token: unexpected character {:?}", stuck)
+                } else {
+                    write!(
+                        f,
+                        "not a token: `{}` can't be followed by {:?}",
+                        text, stuck
+                    )
+                }
+            }
+            UnclosedComment { .. } => write!(f, "unclosed comment; expected `*/`"),
+            IntTooLarge { lexeme, .. } => write!(f, "int literal too large: {}", lexeme),
+            MinIntNotNegated { lexeme, .. } => {
+                write!(f, "int literal too large without unary minus: {}", lexeme)
+            }
+            NotInDialect {
+                lexeme, dialect, ..
+            } => write!(f, "`{}` is not supported in {}", lexeme, dialect),
+            EmptyCharLiteral { .. } => {
+                write!(f, "empty char literal; must have a char between the quotes")
+            }
+            CharLiteralTooLong { lexeme, .. } => write!(
+                f,
+                "char literal too long; expected a single char, found {}",
+                lexeme
+            ),
+            BadUnicodeEscape { lexeme, .. } => write!(f, "malformed unicode escape `{}`", lexeme),
+            BadEscape { error, .. } => write!(f, "{}", error),
+        }
+    }
+}
+
+/// The reason an escape sequence in a string or char literal is invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EscapeError {
+    /// A backslash at the very end of the literal.
+    Empty,
+    /// Not a recognized escape sequence, e.g. `\q`.
+    Unknown(char),
+    /// Numeric escape sequences are octal, so `\8` and `\9` are invalid.
+    NotOctal(char),
+    /// The largest octal escape is `\3777`. These are the digits that were found.
+    OctalTooLarge(String),
+    /// Octal escapes must be in the ASCII range. These are the digits that were found.
+    NotAscii(String),
+}
+
+impl fmt::Display for EscapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use EscapeError::*;
+        match self {
+            Empty => write!(f, "empty escape sequence: backslash at end of literal"),
+            // A unicode escape would have been translated already, if it was well-formed.
+            Unknown('u') => write!(f, "invalid unicode escape; expected 4 hex digits after \\u"),
+            Unknown(c) => write!(f, "invalid or unknown escape sequence: \\{}", c),
+            NotOctal(c) => write!(
+                f,
+                "numeric escape sequences are octal (largest digit is 7), found \\{}",
+                c
+            ),
+            OctalTooLarge(digits) => write!(
+                f,
+                "octal escape too large (largest is \\3777), found \\{}",
+                digits
+            ),
+            NotAscii(digits) => write!(
+                f,
+                "octal escape out of ASCII range (largest is \\177), found \\{}",
+                digits
+            ),
+        }
+    }
+}
+
+impl EscapeError {
+    /// The offending escape sequence, as it appears in the input.
+    fn escape_seq(&self) -> String {
+        use EscapeError::*;
+        match self {
+            Empty => String::from("\\"),
+            Unknown(c) | NotOctal(c) => format!("\\{}", c),
+            OctalTooLarge(digits) | NotAscii(digits) => format!("\\{}", digits),
+        }
+    }
 }
@@ -0,0 +1,181 @@
+//! Decoding of the input text, before it's handed to the DFA.
+//!
+//! Java source goes through "unicode escape" translation before lexing (JLS 3.3): an escape like
+//! `\u0041` anywhere in the file is treated exactly as if it was the character `A`. We don't make a
+//! translated copy of the input; instead, each source character is decoded on the fly from its
+//! position in the original line. That way, columns and lexemes always refer to the original text.
+
+use crate::tokenizer::tokens::TokenError;
+use crate::tokenizer::Position;
+use std::borrow::Cow;
+
+/// Decode raw bytes as UTF-8.
+///
+/// Invalid UTF-8 sequences are replaced with U+FFFD. Since this isn't ASCII, it will be rejected
+/// by the tokenizer, unless it appears in a comment or a string or char literal.
+pub fn decode_utf8(bytes: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(bytes)
+}
+
+/// Apply unicode escape translation to a whole string.
+///
+/// This is for text the tokenizer has already accepted. A malformed escape is left as it is, since
+/// it would have been reported already.
+pub fn translate(s: &str) -> Cow<'_, str> {
+    if !s.contains("\\u") {
+        return Cow::Borrowed(s);
+    }
+
+    Cow::Owned(chars(s).map(|(_, c)| c).collect())
+}
+
+/// Iterate over the characters of `s`, after unicode escape translation.
+///
+/// Each character is paired with its byte offset in `s`, similar to `str::char_indices`. Like
+/// `translate`, this leaves malformed escapes as they are.
+pub fn chars(s: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        if offset == s.len() {
+            return None;
+        }
+
+        let (c, width) = decode_str(s, offset).unwrap_or(('\\', 1));
+        let item = (offset, c);
+        offset += width;
+        Some(item)
+    })
+}
+
+/// Decode the source character at `pos`, applying unicode escape translation.
+///
+/// Returns the decoded character, and the number of bytes it takes up in the line. The position
+/// just past the end of the line is a newline, with no width.
+///
+/// It's an error for a backslash that's eligible to start an escape to be followed by `u`s, but not
+/// then by four hex digits (e.g. `\u00G1`). This goes for the whole file, comments included
+/// (JLS 3.3).
+///
+/// Escapes of UTF-16 surrogates (e.g. `\uD834`) decode to U+FFFD, since they aren't valid chars on
+/// their own. These are never valid in Joos anyways.
+pub fn decode(pos: Position) -> Result<(char, usize), TokenError> {
+    assert!(pos.col <= pos.line.len());
+    if pos.col == pos.line.len() {
+        return Ok(('\n', 0));
+    }
+
+    decode_str(pos.line, pos.col).map_err(|len| TokenError::BadUnicodeEscape {
+        start: pos,
+        lexeme: &pos.line[pos.col..pos.col + len],
+    })
+}
+
+/// Decode the character at byte offset `offset` of `line`. On a malformed escape, returns its
+/// length: the backslash, the `u`s, the hex digits, and the character that should have been one
+/// (unless it's whitespace).
+fn decode_str(line: &str, offset: usize) -> Result<(char, usize), usize> {
+    let rest = &line[offset..];
+    let c = rest.chars().next().expect("offset out of bounds");
+
+    if c == '\\' && is_eligible(line, offset) {
+        if let Some(escape) = unicode_escape(rest) {
+            return escape;
+        }
+    }
+
+    Ok((c, c.len_utf8()))
+}
+
+/// A backslash is only eligible to begin a unicode escape if it's preceded by an even number of
+/// contiguous (raw) backslashes. E.g., `\\u0041` is a backslash followed by `u0041`.
+fn is_eligible(line: &str, offset: usize) -> bool {
+    let num_backslashes = line.as_bytes()[..offset]
+        .iter()
+        .rev()
+        .take_while(|&&b| b == b'\\')
+        .count();
+    num_backslashes % 2 == 0
+}
+
+/// Try to parse a unicode escape at the start of `s`: a backslash, one or more `u`s, then four
+/// hex digits. `None` if there are no `u`s, so that it's not an escape at all.
+fn unicode_escape(s: &str) -> Option<Result<(char, usize), usize>> {
+    let num_us = s[1..].bytes().take_while(|&b| b == b'u').count();
+    if num_us == 0 {
+        return None;
+    }
+
+    let start = 1 + num_us;
+    let num_digits = s[start..]
+        .bytes()
+        .take(4)
+        .take_while(u8::is_ascii_hexdigit)
+        .count();
+    if num_digits < 4 {
+        // Include the offending character, unless it's whitespace.
+        let bad = s[start + num_digits..].chars().next();
+        let bad = bad.filter(|c| !c.is_whitespace()).map_or(0, char::len_utf8);
+        return Some(Err(start + num_digits + bad));
+    }
+
+    let code = u32::from_str_radix(&s[start..start + 4], 16).unwrap();
+    let c = char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER);
+    Some(Ok((c, start + 4)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source_map::FileId;
+
+    #[test]
+    fn escapes() {
+        for (input, expected) in [
+            ("abc", "abc"),
+            (r"\u0041", "A"),
+            (r"x\uuuu0041y", "xAy"),
+            (r"\\u0041", r"\\u0041"),
+            (r"\\\u0041", r"\\A"),
+            (r"\\", r"\\"),
+            (r"\u005c\u0041", r"\A"),
+            ("é\\u00e9", "éé"),
+            (r"\uD834", "\u{FFFD}"),
+        ] {
+            assert_eq!(translate(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn malformed_escapes() {
+        let at = |line, col| Position {
+            file: FileId::ANONYMOUS,
+            line,
+            line_num: 0,
+            line_start: 0,
+            col,
+        };
+        for (line, col, expected) in [
+            (r"\u00G1", 0, r"\u00G"),
+            (r"x\uu004", 1, r"\uu004"),
+            (r"\u00e", 0, r"\u00e"),
+            (r"\u 0041", 0, r"\u"),
+        ] {
+            match decode(at(line, col)) {
+                Err(TokenError::BadUnicodeEscape { start, lexeme }) => {
+                    assert_eq!((start.col, lexeme), (col, expected), "{}", line);
+                }
+                result => panic!("{}: expected an error, got {:?}", line, result),
+            }
+        }
+        // Not eligible, so not an escape at all.
+        assert_eq!(decode(at(r"\\u00G1", 1)), Ok(('\\', 1)));
+        assert_eq!(decode(at(r"\u0041", 0)), Ok(('A', 6)));
+    }
+
+    #[test]
+    fn offsets() {
+        let actual: Vec<_> = chars(r"ab\u00e9\n").collect();
+        let expected = vec![(0, 'a'), (1, 'b'), (2, 'é'), (8, '\\'), (9, 'n')];
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,46 @@
+use cs444::ast::CompilationUnit;
+use cs444::parser::Parser;
+use cs444::tokenizer::{Text, Tokenizer};
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fs;
+use walkdir::WalkDir;
+
+type Res<T> = Result<T, Box<dyn Error>>;
+
+/// Every example of a Joos 1W feature should parse, and lower to an AST.
+#[test]
+fn joos_features() -> Res<()> {
+    let base_dir = format!(
+        "{}/tests/good-examples/Joos-1W-features",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let tokenizer = Tokenizer::new();
+    let parser = Parser::joos_1w();
+
+    let mut count = 0;
+    for entry in WalkDir::new(base_dir) {
+        let path = entry?.into_path();
+        if path.extension().and_then(OsStr::to_str) != Some("java") {
+            continue;
+        }
+        let src = fs::read_to_string(&path)?;
+        let (tokens, errors) = tokenizer.tokenize_all(Text(&src));
+        assert!(errors.is_empty(), "{:?}", path);
+
+        let num_tokens = tokens.len();
+        let tree = parser
+            .parse(tokens)
+            .map_err(|e| format!("{:?}: {}", path, e))?;
+        assert_eq!(num_tokens, tree.tokens().len(), "{:?}", path);
+
+        let unit = CompilationUnit::lower(&tree, parser.table().grammar())
+            .map_err(|e| format!("{:?}: {}", path, e))?;
+        assert_eq!(tree.span(), Some(unit.span), "{:?}", path);
+        count += 1;
+    }
+
+    // Sanity check that we actually found something.
+    assert!(count >= 60);
+    Ok(())
+}
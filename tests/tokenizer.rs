@@ -1,11 +1,13 @@
-use cs444::tokenizer::token_types::Literal::{Bool, Char, Int, Null, StringLit};
-use cs444::tokenizer::tokens::{Token, TokenOrComment};
-use cs444::tokenizer::Tokenizer;
+use cs444::source_map;
+use cs444::tokenizer::token_types::Literal::{
+    Bool, Char, Double, Float, HexInt, Int, Long, Null, OctalInt, StringLit,
+};
+use cs444::tokenizer::tokens::{Token, TokenInfo, TokenOrComment};
+use cs444::tokenizer::{Dialect, Text, Tokenizer};
 use std::error::Error;
 use std::ffi::OsStr;
-use std::fs::File;
-use std::io::{prelude::*, BufReader};
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
 type Res<T> = Result<T, Box<dyn Error>>;
@@ -13,25 +15,102 @@ type Res<T> = Result<T, Box<dyn Error>>;
 /// Run `tokenize_and_echo` to validate all the `.java` files in `tests/good-examples`.
 #[test]
 fn good_examples() -> Res<()> {
-    let base_dir = format!("{}/tests/good-examples", env!("CARGO_MANIFEST_DIR"));
+    let tokenizer = Tokenizer::new();
+    for path in good_examples_files()? {
+        tokenize_and_echo(&tokenizer, path)?;
+    }
+    Ok(())
+}
 
-    let mut tests_run = 0;
+/// The good examples are Java too, so they should also tokenize as Java 1.3. Some of them use
+/// literals that Joos doesn't have.
+#[test]
+fn good_examples_java() -> Res<()> {
+    let tokenizer = Tokenizer::with_dialect(Dialect::Java1_3);
+    for path in good_examples_files()? {
+        tokenize_and_echo(&tokenizer, &path)?;
+    }
+
+    let path = format!(
+        "{}/tests/good-examples/DNSSEC.java",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let src = fs::read_to_string(path)?;
+    let longs = tokenizer
+        .tokenize(Text(&src))
+        .filter(|t| {
+            matches!(
+                t,
+                Ok(TokenInfo {
+                    val: Token::Literal(Long(_)),
+                    ..
+                })
+            )
+        })
+        .count();
+    assert_eq!(4, longs);
+    Ok(())
+}
 
+/// The examples of Joos 1W features shouldn't use anything Joos doesn't support.
+#[test]
+fn joos_features_supported() -> Res<()> {
     let tokenizer = Tokenizer::new();
+    for path in good_examples_files()? {
+        if !path.to_string_lossy().contains("Joos-1W-features") {
+            continue;
+        }
+        let src = fs::read_to_string(&path)?;
+        let (tokens, errors) = tokenizer.tokenize_all(Text(&src));
+        assert!(errors.is_empty(), "{:?}", path);
+        let unsupported = tokenizer.check_supported(&tokens);
+        assert!(unsupported.is_empty(), "{:?}: {}", path, unsupported[0]);
+    }
+    Ok(())
+}
+
+/// Round-trip all the good examples through `tokenize_lossless`, with each kind of line
+/// terminator, and with tabs and form feeds thrown in.
+#[test]
+fn good_examples_lossless() -> Res<()> {
+    let tokenizer = Tokenizer::new();
+    for path in good_examples_files()? {
+        let src = fs::read_to_string(&path)?;
+        let lines: Vec<&str> = source_map::lines(&src).map(|(_, line)| line).collect();
+        let variants = [
+            src.clone(),
+            lines.join("\r\n"),
+            lines.join("\r") + "\r",
+            lines.join("\n").replace("    ", "\t") + "\x0c",
+        ];
+        for src in variants {
+            let file = tokenizer
+                .tokenize_lossless(Text(&src))
+                .map_err(|e| format!("{:?}: {}", path, e[0]))?;
+            assert_eq!(src, file.to_string(), "{:?}", path);
+        }
+    }
+    Ok(())
+}
+
+/// All the `.java` files in `tests/good-examples`.
+fn good_examples_files() -> Res<Vec<PathBuf>> {
+    let base_dir = format!("{}/tests/good-examples", env!("CARGO_MANIFEST_DIR"));
+
+    let mut files = vec![];
     for entry in WalkDir::new(base_dir) {
         let entry = entry?;
         if entry.file_type().is_file()
             && entry.path().extension().and_then(OsStr::to_str) == Some("java")
         {
-            tokenize_and_echo(&tokenizer, entry.path())?;
-            tests_run += 1;
+            files.push(entry.into_path());
         }
     }
 
-    // Sanity check that the above loop actually did something.
-    assert!(tests_run >= 10);
+    // Sanity check that we actually found something.
+    assert!(files.len() >= 10);
 
-    Ok(())
+    Ok(files)
 }
 
 /// Tokenize the input file, then write these tokens back to a string. Compare the output string
@@ -39,12 +118,21 @@ fn good_examples() -> Res<()> {
 ///
 /// Panics or returns an error on failure.
 fn tokenize_and_echo(tokenizer: &Tokenizer, input_file: impl AsRef<Path>) -> Res<()> {
-    let input = BufReader::new(File::open(&input_file)?);
-    let input: Vec<String> = input.lines().collect::<Result<_, _>>()?;
+    let src = fs::read_to_string(&input_file)?;
+    let input: Vec<&str> = source_map::lines(&src).map(|(_, line)| line).collect();
     let mut output = Vec::<String>::with_capacity(input.len());
 
-    for elem in tokenizer.tokenize_keep_comments(input.iter().map(String::as_str)) {
+    for elem in tokenizer.tokenize_keep_comments(Text(&src)) {
+        let elem = elem.map_err(|e| format!("{:?}: {}", input_file.as_ref(), e))?;
         sanity_check(&elem);
+        let snippet = &src[elem.span().lo as usize..elem.span().hi as usize];
+        match &elem {
+            TokenOrComment::Token(token) => assert_eq!(token.lexeme, snippet),
+            _ => {
+                let snippet = snippet.replace("\r\n", "\n").replace('\r', "\n");
+                assert_eq!(elem.comment().unwrap().text, snippet);
+            }
+        }
         echo_elem(&input, elem, &mut output);
     }
 
@@ -77,7 +165,7 @@ fn tokenize_and_echo(tokenizer: &Tokenizer, input_file: impl AsRef<Path>) -> Res
 /// Append an input element (token or comment) to the output buffer.
 ///
 /// Adds whitespace preceeding the element, according to its start position in the line.
-fn echo_elem(input: &[String], elem: TokenOrComment, output: &mut Vec<String>) {
+fn echo_elem(input: &[&str], elem: TokenOrComment, output: &mut Vec<String>) {
     // Add newline(s) to `output` to make room for `elem`.
     while elem.start().line_num >= output.len() {
         let line_len = input[output.len()].len();
@@ -91,36 +179,15 @@ fn echo_elem(input: &[String], elem: TokenOrComment, output: &mut Vec<String>) {
     let num_spaces = elem.start().col - buf.len();
     buf.push_str(&" ".repeat(num_spaces));
 
-    match elem {
+    match &elem {
         TokenOrComment::Token(token) => {
             buf.push_str(token.lexeme);
         }
-        TokenOrComment::LineComment { start } => {
-            let lexeme = &start.line[start.col..];
-            buf.push_str(lexeme);
-        }
-        TokenOrComment::StarComment {
-            start,
-            end_inclusive,
-        } => {
-            // One-line.
-            if start.line_num == end_inclusive.line_num {
-                let lexeme = &start.line[start.col..=end_inclusive.col];
-                buf.push_str(lexeme);
-            }
-            // Multi-line.
-            else {
-                let first = &start.line[start.col..];
-                buf.push_str(first);
-
-                // (Middle lines.)
-                for i in start.line_num + 1..end_inclusive.line_num {
-                    output.push(String::from(&input[i]));
-                }
-
-                let last = &end_inclusive.line[..=end_inclusive.col];
-                output.push(String::from(last));
-            }
+        _ => {
+            // Comments may span several lines.
+            let mut lines = elem.comment().unwrap().text.split('\n');
+            buf.push_str(lines.next().unwrap());
+            output.extend(lines.map(String::from));
         }
     }
 }
@@ -129,38 +196,48 @@ fn echo_elem(input: &[String], elem: TokenOrComment, output: &mut Vec<String>) {
 fn sanity_check<'a>(elem: &TokenOrComment<'a>) {
     use TokenOrComment::*;
     match elem {
-        Token(token) => assert_eq!(token.lexeme, token_to_str(&token.val), "{:?}", token),
-        LineComment { start } => {
-            assert_eq!("//", &start.line[start.col..start.col + 2]);
-        }
-        StarComment {
-            start,
-            end_inclusive,
-        } => {
-            assert_eq!("/*", &start.line[start.col..start.col + 2]);
+        Token(token) => assert_eq!(value_text(token), token_to_str(&token.val), "{:?}", token),
+        LineComment(c) => assert!(c.text.starts_with("//")),
+        BlockComment(c) | DocComment(c) => {
+            assert!(c.text.starts_with("/*"));
+            assert_eq!(
+                matches!(elem, DocComment(_)),
+                c.text.starts_with("/**") && c.text != "/**/"
+            );
 
             // (We could also check that there are no "*/"s in the middle,
             // but I'm fine with what we have; no need to overdo it.)
-
-            let end = end_inclusive;
-            assert_eq!("*/", &end.line[end.col - 1..=end.col]);
+            assert!(c.text.ends_with("*/"));
         }
     }
 }
 
+/// The token's lexeme, minus the type suffix of a Java long, float or double literal, since the
+/// value leaves that out.
+fn value_text<'a>(token: &TokenInfo<'a>) -> &'a str {
+    match token.val {
+        Token::Literal(Long(_) | Float(_) | Double(_)) => token
+            .lexeme
+            .strip_suffix(['l', 'L', 'f', 'F', 'd', 'D'])
+            .unwrap_or(token.lexeme),
+        _ => token.lexeme,
+    }
+}
+
 /// Convert a token's inner value to a reasonable string representation.
 fn token_to_str<'a>(token: &Token<'a>) -> String {
     match token {
-        Token::Identifier(name) => String::from(*name),
+        Token::Identifier(name) => name.to_string(),
         Token::Keyword(k) => k.to_string(),
         Token::Separator(s) => s.to_string(),
         Token::Operator(o) => o.to_string(),
         Token::Literal(l) => match l {
-            Int(n) => n.to_string(),
+            Int(n) => n.clone(),
+            HexInt(s) | OctalInt(s) | Long(s) | Float(s) | Double(s) => s.clone(),
             Bool(b) => b.to_string(),
             Char(c) => format!("'{}'", unescape_str(&c.to_string())),
-            StringLit(s) => format!("\"{}\"", unescape_str(&s)),
-            Null => format!("null"),
+            StringLit(s) => format!("\"{}\"", unescape_str(s)),
+            Null => String::from("null"),
         },
     }
 }